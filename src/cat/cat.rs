@@ -0,0 +1,65 @@
+use reqwest::{header::RANGE, StatusCode};
+use scienceobjectsdb_rust_api::sciobjectsdb::sciobjsdb::api::storage::services::v1::CreateDownloadLinkRequest;
+use tokio::io::AsyncWriteExt;
+
+use crate::{
+    client::client,
+    util::{
+        cli,
+        error::{CliError, Result},
+    },
+};
+
+/// Streams the data of a single object to stdout
+pub struct Cat {
+    client: client::Client,
+}
+
+impl Cat {
+    pub fn new(client: client::Client) -> Self {
+        return Cat { client: client };
+    }
+
+    pub async fn cat(&self, request: cli::Cat) -> Result<()> {
+        let object_link = self
+            .client
+            .call(
+                &self.client.object_load_service,
+                CreateDownloadLinkRequest {
+                    id: request.id,
+                    ..Default::default()
+                },
+                |mut service, req| async move { service.create_download_link(req).await },
+            )
+            .await?;
+
+        let http_client = reqwest::Client::new();
+        let mut get_request = http_client.get(object_link.download_link);
+        if let Some(range) = &request.range {
+            get_request = get_request.header(RANGE, range.header_value());
+        }
+        let mut get_response = get_request.send().await?.error_for_status()?;
+
+        // a storage that ignores the range would write the whole object
+        if request.range.is_some() && get_response.status() != StatusCode::PARTIAL_CONTENT {
+            return Err(CliError::InvalidResponse(
+                "the storage did not honor the requested byte range".to_string(),
+            ));
+        }
+
+        let mut stdout = tokio::io::stdout();
+        while let Some(chunk) = get_response.chunk().await? {
+            self.client.rate_limiter.acquire(chunk.len()).await;
+            match stdout.write_all(&chunk).await {
+                // the reading end was closed, e.g. by `head`
+                Err(err) if err.kind() == std::io::ErrorKind::BrokenPipe => return Ok(()),
+                result => result?,
+            }
+        }
+
+        match stdout.flush().await {
+            Err(err) if err.kind() == std::io::ErrorKind::BrokenPipe => Ok(()),
+            result => Ok(result?),
+        }
+    }
+}
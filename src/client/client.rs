@@ -6,11 +6,17 @@ use scienceobjectsdb_rust_api::sciobjectsdb::sciobjsdb::api::{
     },
 };
 
+use std::future::Future;
+
 use tonic::codegen::InterceptedService;
 use tonic::metadata::AsciiMetadataKey;
 use tonic::metadata::AsciiMetadataValue;
 use tonic::transport::Channel;
 
+use crate::util::error::{self, CliError};
+
+use super::{rate_limit::RateLimiter, retry::RetryPolicy};
+
 const API_TOKEN_ENTRY_KEY: &str = "API_TOKEN";
 
 #[derive(Clone)]
@@ -30,15 +36,25 @@ pub struct Client {
     pub notification_service: update_notification_service_client::UpdateNotificationServiceClient<
         InterceptedService<Channel, ClientInterceptor>,
     >,
+    pub retry_policy: RetryPolicy,
+    pub rate_limiter: RateLimiter,
 }
 
 #[derive(Clone)]
 pub struct ClientInterceptor {
-    api_token: String,
+    api_token: AsciiMetadataValue,
 }
 
 impl Client {
-    pub async fn new(channel: Channel, api_token: String) -> Self {
+    pub async fn new(
+        channel: Channel,
+        api_token: String,
+        retry_policy: RetryPolicy,
+        rate_limiter: RateLimiter,
+    ) -> error::Result<Self> {
+        let api_token = AsciiMetadataValue::try_from(api_token.as_str()).map_err(|_| {
+            CliError::Config("api_key contains characters that are not allowed".to_string())
+        })?;
         let interceptor = ClientInterceptor {
             api_token: api_token,
         };
@@ -49,9 +65,34 @@ impl Client {
             dataset_object_service: dataset_objects_service_client::DatasetObjectsServiceClient::with_interceptor(channel.clone(), interceptor.clone()),
             object_load_service: object_load_service_client::ObjectLoadServiceClient::with_interceptor(channel.clone(), interceptor.clone()),
             notification_service: update_notification_service_client::UpdateNotificationServiceClient::with_interceptor(channel, interceptor.clone()),
+            retry_policy: retry_policy,
+            rate_limiter: rate_limiter,
         };
 
-        return client;
+        return Ok(client);
+    }
+
+    /// Calls the given method of the service and retries it according to the retry policy.
+    /// Only idempotent calls should be made through this method, e.g.
+    /// `client.call(&client.dataset_service, request, |mut s, r| async move { s.get_dataset(r).await })`
+    pub async fn call<S, Req, T, F, Fut>(
+        &self,
+        service: &S,
+        request: Req,
+        method: F,
+    ) -> error::Result<T>
+    where
+        S: Clone,
+        Req: Clone,
+        F: Fn(S, Req) -> Fut,
+        Fut: Future<Output = Result<tonic::Response<T>, tonic::Status>>,
+    {
+        self.retry_policy
+            .retry(|_| {
+                let response = method(service.clone(), request.clone());
+                async move { Ok(response.await?.into_inner()) }
+            })
+            .await
     }
 }
 
@@ -61,7 +102,7 @@ impl tonic::service::Interceptor for ClientInterceptor {
         let metadata = mut_req.metadata_mut();
         metadata.append(
             AsciiMetadataKey::from_bytes(API_TOKEN_ENTRY_KEY.as_bytes()).unwrap(),
-            AsciiMetadataValue::try_from(self.api_token.as_str()).unwrap(),
+            self.api_token.clone(),
         );
 
         return Ok(mut_req);
@@ -0,0 +1,221 @@
+use std::sync::{Arc, Mutex};
+
+use scienceobjectsdb_rust_api::sciobjectsdb::sciobjsdb::api::storage::{
+    models::v1::{Dataset, Object, ObjectGroup, ObjectGroupRevision},
+    services::v1::{
+        dataset_service_server::{DatasetService, DatasetServiceServer},
+        CreateDatasetRequest, CreateDatasetResponse, DeleteDatasetRequest, DeleteDatasetResponse,
+        DeleteDatasetVersionRequest, DeleteDatasetVersionResponse, GetDatasetObjectGroupsRequest,
+        GetDatasetObjectGroupsResponse, GetDatasetObjectsRequest, GetDatasetObjectsResponse,
+        GetDatasetRequest, GetDatasetResponse, GetDatasetVersionObjectGroupsRequest,
+        GetDatasetVersionObjectGroupsResponse, GetDatasetVersionRequest, GetDatasetVersionResponse,
+        GetDatasetVersionsRequest, GetDatasetVersionsResponse,
+        GetObjectGroupRevisionsInDateRangeRequest, GetObjectGroupRevisionsInDateRangeResponse,
+        GetObjectGroupsStreamLinkRequest, GetObjectGroupsStreamLinkResponse,
+        ReleaseDatasetVersionRequest, ReleaseDatasetVersionResponse, UpdateDatasetFieldRequest,
+        UpdateDatasetFieldResponse,
+    },
+};
+use tonic::{
+    transport::{Channel, Server},
+    Request, Response, Status,
+};
+
+use crate::{
+    client::{client, rate_limit::RateLimiter, retry::RetryPolicy},
+    util::config::RetryConfig,
+};
+
+/// Dataset service that pages its object groups by id like the API, the cursors of all
+/// requests are recorded
+pub struct MockDatasetService {
+    object_groups: Vec<ObjectGroup>,
+    ignore_cursor: bool,
+    cursors: Arc<Mutex<Vec<String>>>,
+}
+
+#[tonic::async_trait]
+impl DatasetService for MockDatasetService {
+    async fn get_dataset_object_groups(
+        &self,
+        request: Request<GetDatasetObjectGroupsRequest>,
+    ) -> Result<Response<GetDatasetObjectGroupsResponse>, Status> {
+        let request = request.into_inner();
+        let page_request = request.page_request.unwrap_or_default();
+        self.cursors
+            .lock()
+            .unwrap()
+            .push(page_request.last_uuid.clone());
+
+        let object_groups = self
+            .object_groups
+            .iter()
+            .filter(|x| self.ignore_cursor || x.id > page_request.last_uuid)
+            .filter(|x| match (&request.label_filter, &x.current_revision) {
+                (None, _) => true,
+                (Some(label), Some(revision)) => revision.labels.contains(label),
+                (Some(_), None) => false,
+            })
+            .take(page_request.page_size as usize)
+            .cloned()
+            .collect();
+
+        return Ok(Response::new(GetDatasetObjectGroupsResponse {
+            object_groups: object_groups,
+        }));
+    }
+
+    async fn get_dataset(
+        &self,
+        request: Request<GetDatasetRequest>,
+    ) -> Result<Response<GetDatasetResponse>, Status> {
+        return Ok(Response::new(GetDatasetResponse {
+            dataset: Some(Dataset {
+                id: request.into_inner().id,
+                name: "dataset".to_string(),
+                ..Default::default()
+            }),
+        }));
+    }
+
+    async fn get_dataset_version_object_groups(
+        &self,
+        _: Request<GetDatasetVersionObjectGroupsRequest>,
+    ) -> Result<Response<GetDatasetVersionObjectGroupsResponse>, Status> {
+        return Err(Status::unimplemented("not mocked"));
+    }
+
+    async fn get_dataset_objects(
+        &self,
+        _: Request<GetDatasetObjectsRequest>,
+    ) -> Result<Response<GetDatasetObjectsResponse>, Status> {
+        return Err(Status::unimplemented("not mocked"));
+    }
+
+    async fn get_dataset_versions(
+        &self,
+        _: Request<GetDatasetVersionsRequest>,
+    ) -> Result<Response<GetDatasetVersionsResponse>, Status> {
+        return Err(Status::unimplemented("not mocked"));
+    }
+
+    async fn get_dataset_version(
+        &self,
+        _: Request<GetDatasetVersionRequest>,
+    ) -> Result<Response<GetDatasetVersionResponse>, Status> {
+        return Err(Status::unimplemented("not mocked"));
+    }
+
+    async fn create_dataset(
+        &self,
+        _: Request<CreateDatasetRequest>,
+    ) -> Result<Response<CreateDatasetResponse>, Status> {
+        return Err(Status::unimplemented("not mocked"));
+    }
+
+    async fn release_dataset_version(
+        &self,
+        _: Request<ReleaseDatasetVersionRequest>,
+    ) -> Result<Response<ReleaseDatasetVersionResponse>, Status> {
+        return Err(Status::unimplemented("not mocked"));
+    }
+
+    async fn get_object_groups_stream_link(
+        &self,
+        _: Request<GetObjectGroupsStreamLinkRequest>,
+    ) -> Result<Response<GetObjectGroupsStreamLinkResponse>, Status> {
+        return Err(Status::unimplemented("not mocked"));
+    }
+
+    async fn update_dataset_field(
+        &self,
+        _: Request<UpdateDatasetFieldRequest>,
+    ) -> Result<Response<UpdateDatasetFieldResponse>, Status> {
+        return Err(Status::unimplemented("not mocked"));
+    }
+
+    async fn delete_dataset(
+        &self,
+        _: Request<DeleteDatasetRequest>,
+    ) -> Result<Response<DeleteDatasetResponse>, Status> {
+        return Err(Status::unimplemented("not mocked"));
+    }
+
+    async fn get_object_group_revisions_in_date_range(
+        &self,
+        _: Request<GetObjectGroupRevisionsInDateRangeRequest>,
+    ) -> Result<Response<GetObjectGroupRevisionsInDateRangeResponse>, Status> {
+        return Err(Status::unimplemented("not mocked"));
+    }
+
+    async fn delete_dataset_version(
+        &self,
+        _: Request<DeleteDatasetVersionRequest>,
+    ) -> Result<Response<DeleteDatasetVersionResponse>, Status> {
+        return Err(Status::unimplemented("not mocked"));
+    }
+}
+
+/// Object groups with the ids `og0000`, `og0001`, ..., every group has one object
+pub fn object_groups(count: usize) -> Vec<ObjectGroup> {
+    return (0..count)
+        .map(|i| {
+            let id = format!("og{:04}", i);
+            ObjectGroup {
+                id: id.clone(),
+                current_revision: Some(ObjectGroupRevision {
+                    id: format!("{}-rev", id),
+                    name: id.clone(),
+                    object_group_id: id.clone(),
+                    objects: vec![Object {
+                        id: format!("{}-object", id),
+                        ..Default::default()
+                    }],
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }
+        })
+        .collect();
+}
+
+/// Starts the mock on a free local port and returns a client for it and the recorded cursors
+pub async fn mock_client(
+    object_groups: Vec<ObjectGroup>,
+    ignore_cursor: bool,
+) -> (client::Client, Arc<Mutex<Vec<String>>>) {
+    let cursors = Arc::new(Mutex::new(Vec::new()));
+    let service = MockDatasetService {
+        object_groups: object_groups,
+        ignore_cursor: ignore_cursor,
+        cursors: cursors.clone(),
+    };
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let incoming = futures::stream::unfold(listener, |listener| async move {
+        let stream = listener.accept().await.map(|(stream, _)| stream);
+        Some((stream, listener))
+    });
+    tokio::spawn(
+        Server::builder()
+            .add_service(DatasetServiceServer::new(service))
+            .serve_with_incoming(incoming),
+    );
+
+    let channel = Channel::from_shared(format!("http://{}", addr))
+        .unwrap()
+        .connect()
+        .await
+        .unwrap();
+    let client = client::Client::new(
+        channel,
+        "token".to_string(),
+        RetryPolicy::from_config(&RetryConfig::default()).unwrap(),
+        RateLimiter::new(None),
+    )
+    .await
+    .unwrap();
+
+    return (client, cursors);
+}
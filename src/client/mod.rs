@@ -1 +1,6 @@
 pub mod client;
+#[cfg(test)]
+pub mod mock;
+pub mod paging;
+pub mod rate_limit;
+pub mod retry;
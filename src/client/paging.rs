@@ -0,0 +1,383 @@
+use std::future::Future;
+
+use futures::{stream, Stream, TryStreamExt};
+use scienceobjectsdb_rust_api::sciobjectsdb::sciobjsdb::api::storage::{
+    models::v1::{LabelFilter, Object, ObjectGroup, ObjectGroupRevision, PageRequest},
+    services::v1::{
+        GetDatasetObjectGroupsRequest, GetDatasetObjectsRequest,
+        GetDatasetVersionObjectGroupsRequest,
+    },
+};
+
+use crate::{
+    client::client,
+    util::{
+        cli::{self, LabelSelector},
+        error::{CliError, Result},
+    },
+};
+
+/// Page size of listings that are read completely
+pub const DEFAULT_PAGE_SIZE: u64 = 500;
+
+/// Entries of a listing that is paged by the id of the last entry of the previous page
+pub trait PagedEntry {
+    fn page_id(&self) -> &str;
+}
+
+impl PagedEntry for ObjectGroup {
+    fn page_id(&self) -> &str {
+        return &self.id;
+    }
+}
+
+impl PagedEntry for ObjectGroupRevision {
+    fn page_id(&self) -> &str {
+        return &self.id;
+    }
+}
+
+impl PagedEntry for Object {
+    fn page_id(&self) -> &str {
+        return &self.id;
+    }
+}
+
+/// Lists the object groups of a dataset that match the labels page by page, starting after
+/// the object group with the id `after`. The server filters object groups by a single label,
+/// the remaining labels are checked against the current revision of every object group
+pub fn dataset_object_group_pages(
+    client: client::Client,
+    dataset_id: String,
+    labels: Vec<LabelSelector>,
+    page_size: u64,
+    after: String,
+) -> impl Stream<Item = Result<Vec<ObjectGroup>>> {
+    let resource = format!("object groups of dataset {}", dataset_id);
+    let label_filter = labels.first().map(|x| x.to_proto_label());
+    let remaining_labels = labels.iter().skip(1).cloned().collect::<Vec<_>>();
+
+    let pages = pages(resource, page_size, after, move |page_request| {
+        let client = client.clone();
+        let request = GetDatasetObjectGroupsRequest {
+            id: dataset_id.clone(),
+            page_request: Some(page_request),
+            label_filter: label_filter.clone(),
+        };
+
+        async move {
+            let response = client
+                .call(
+                    &client.dataset_service,
+                    request,
+                    |mut service, req| async move { service.get_dataset_object_groups(req).await },
+                )
+                .await?;
+            Ok(response.object_groups)
+        }
+    });
+
+    // pages are filtered after they were requested, the next page still has to continue after
+    // the last object group the server returned
+    return pages.map_ok(move |page| {
+        if remaining_labels.is_empty() {
+            return page;
+        }
+
+        return page
+            .into_iter()
+            .filter(|object_group| match &object_group.current_revision {
+                Some(revision) => cli::matches_labels(&remaining_labels, &revision.labels),
+                None => false,
+            })
+            .collect();
+    });
+}
+
+/// Lists the object group revisions of a dataset version page by page, starting after the
+/// revision with the id `after`
+pub fn dataset_version_object_group_pages(
+    client: client::Client,
+    dataset_version_id: String,
+    page_size: u64,
+    after: String,
+) -> impl Stream<Item = Result<Vec<ObjectGroupRevision>>> {
+    let resource = format!("object groups of dataset version {}", dataset_version_id);
+    return pages(resource, page_size, after, move |page_request| {
+        let client = client.clone();
+        let request = GetDatasetVersionObjectGroupsRequest {
+            id: dataset_version_id.clone(),
+            page_request: Some(page_request),
+        };
+
+        async move {
+            let response = client
+                .call(
+                    &client.dataset_service,
+                    request,
+                    |mut service, req| async move {
+                        service.get_dataset_version_object_groups(req).await
+                    },
+                )
+                .await?;
+            Ok(response.object_group_revisions)
+        }
+    });
+}
+
+/// Lists the objects of a dataset that match the label filter page by page, starting after
+/// the object with the id `after`
+pub fn dataset_object_pages(
+    client: client::Client,
+    dataset_id: String,
+    label_filter: Option<LabelFilter>,
+    page_size: u64,
+    after: String,
+) -> impl Stream<Item = Result<Vec<Object>>> {
+    let resource = format!("objects of dataset {}", dataset_id);
+    return pages(resource, page_size, after, move |page_request| {
+        let client = client.clone();
+        let request = GetDatasetObjectsRequest {
+            id: dataset_id.clone(),
+            page_request: Some(page_request),
+            label_filter: label_filter.clone(),
+        };
+
+        async move {
+            let response = client
+                .call(
+                    &client.dataset_service,
+                    request,
+                    |mut service, req| async move { service.get_dataset_objects(req).await },
+                )
+                .await?;
+            Ok(response.objects)
+        }
+    });
+}
+
+/// All object groups of a dataset that match the labels
+pub fn dataset_object_groups(
+    client: client::Client,
+    dataset_id: String,
+    labels: Vec<LabelSelector>,
+) -> impl Stream<Item = Result<ObjectGroup>> {
+    let pages = dataset_object_group_pages(
+        client,
+        dataset_id,
+        labels,
+        DEFAULT_PAGE_SIZE,
+        "".to_string(),
+    );
+    return entries(pages);
+}
+
+/// All object group revisions of a dataset version
+pub fn dataset_version_object_groups(
+    client: client::Client,
+    dataset_version_id: String,
+) -> impl Stream<Item = Result<ObjectGroupRevision>> {
+    let pages = dataset_version_object_group_pages(
+        client,
+        dataset_version_id,
+        DEFAULT_PAGE_SIZE,
+        "".to_string(),
+    );
+    return entries(pages);
+}
+
+fn entries<T>(pages: impl Stream<Item = Result<Vec<T>>>) -> impl Stream<Item = Result<T>> {
+    return pages
+        .map_ok(|page| stream::iter(page.into_iter().map(Ok)))
+        .try_flatten();
+}
+
+/// Requests the pages of a listing until a page is empty or not full. Every page continues
+/// after the id of the last entry of the previous page, empty pages are not returned
+fn pages<T, F, Fut>(
+    resource: String,
+    page_size: u64,
+    after: String,
+    mut fetch: F,
+) -> impl Stream<Item = Result<Vec<T>>>
+where
+    T: PagedEntry,
+    F: FnMut(PageRequest) -> Fut,
+    Fut: Future<Output = Result<Vec<T>>>,
+{
+    async_stream::try_stream! {
+        let mut prev_last_uuid = after;
+
+        loop {
+            let page = fetch(PageRequest {
+                page_size: page_size,
+                last_uuid: prev_last_uuid.clone(),
+            })
+            .await?;
+
+            let page_len = page.len() as u64;
+            let last_uuid = match page.last() {
+                Some(entry) => entry.page_id().to_string(),
+                None => break,
+            };
+            // a server that ignores the cursor would return the same page forever
+            if last_uuid == prev_last_uuid {
+                Err(CliError::InvalidResponse(format!(
+                    "{} did not advance after {}",
+                    resource, last_uuid
+                )))?;
+            }
+
+            yield page;
+
+            if page_len < page_size {
+                break;
+            }
+            prev_last_uuid = last_uuid;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::TryStreamExt;
+    use scienceobjectsdb_rust_api::sciobjectsdb::sciobjsdb::api::storage::models::v1::{
+        Label, ObjectGroup,
+    };
+
+    use crate::{
+        client::mock::{mock_client, object_groups},
+        util::{cli::LabelSelector, error::CliError},
+    };
+
+    fn ids(object_groups: &[ObjectGroup]) -> Vec<String> {
+        return object_groups.iter().map(|x| x.id.clone()).collect();
+    }
+
+    #[tokio::test]
+    async fn pages_continue_after_the_last_object_group() {
+        let (client, cursors) = mock_client(object_groups(5), false).await;
+
+        let pages: Vec<Vec<ObjectGroup>> = super::dataset_object_group_pages(
+            client,
+            "ds".to_string(),
+            Vec::new(),
+            2,
+            "".to_string(),
+        )
+        .try_collect()
+        .await
+        .unwrap();
+
+        let pages: Vec<Vec<String>> = pages.iter().map(|x| ids(x)).collect();
+        assert_eq!(
+            pages,
+            vec![
+                vec!["og0000", "og0001"],
+                vec!["og0002", "og0003"],
+                vec!["og0004"]
+            ]
+        );
+        assert_eq!(*cursors.lock().unwrap(), vec!["", "og0001", "og0003"]);
+    }
+
+    #[tokio::test]
+    async fn pages_start_after_the_given_object_group() {
+        let (client, cursors) = mock_client(object_groups(5), false).await;
+
+        let pages: Vec<Vec<ObjectGroup>> = super::dataset_object_group_pages(
+            client,
+            "ds".to_string(),
+            Vec::new(),
+            2,
+            "og0002".to_string(),
+        )
+        .try_collect()
+        .await
+        .unwrap();
+
+        let pages: Vec<Vec<String>> = pages.iter().map(|x| ids(x)).collect();
+        assert_eq!(pages, vec![vec!["og0003", "og0004"]]);
+        assert_eq!(*cursors.lock().unwrap(), vec!["og0002", "og0004"]);
+    }
+
+    #[tokio::test]
+    async fn empty_final_page_ends_the_listing() {
+        let (client, cursors) = mock_client(object_groups(4), false).await;
+
+        let pages: Vec<Vec<ObjectGroup>> = super::dataset_object_group_pages(
+            client,
+            "ds".to_string(),
+            Vec::new(),
+            2,
+            "".to_string(),
+        )
+        .try_collect()
+        .await
+        .unwrap();
+
+        // the empty page after the last full one is not returned
+        let pages: Vec<Vec<String>> = pages.iter().map(|x| ids(x)).collect();
+        assert_eq!(
+            pages,
+            vec![vec!["og0000", "og0001"], vec!["og0002", "og0003"]]
+        );
+        assert_eq!(*cursors.lock().unwrap(), vec!["", "og0001", "og0003"]);
+    }
+
+    #[tokio::test]
+    async fn cursor_that_does_not_advance_fails() {
+        let (client, cursors) = mock_client(object_groups(2), true).await;
+
+        let result: crate::util::error::Result<Vec<Vec<ObjectGroup>>> =
+            super::dataset_object_group_pages(
+                client,
+                "ds".to_string(),
+                Vec::new(),
+                2,
+                "".to_string(),
+            )
+            .try_collect()
+            .await;
+
+        assert!(matches!(result, Err(CliError::InvalidResponse(_))));
+        assert_eq!(*cursors.lock().unwrap(), vec!["", "og0001"]);
+    }
+
+    #[tokio::test]
+    async fn labels_after_the_first_are_checked_by_the_client() {
+        let label = |key: &str, value: &str| Label {
+            key: key.to_string(),
+            value: value.to_string(),
+        };
+        let mut object_groups = object_groups(5);
+        for (i, object_group) in object_groups.iter_mut().enumerate() {
+            let revision = object_group.current_revision.as_mut().unwrap();
+            revision.labels = vec![label("kind", "raw"), label("part", &(i % 2).to_string())];
+        }
+        object_groups[2].current_revision.as_mut().unwrap().labels = vec![label("part", "0")];
+        let (client, cursors) = mock_client(object_groups, false).await;
+
+        let labels = vec![
+            LabelSelector {
+                key: "kind".to_string(),
+                value: "raw".to_string(),
+            },
+            LabelSelector {
+                key: "part".to_string(),
+                value: "0".to_string(),
+            },
+        ];
+        let pages: Vec<Vec<ObjectGroup>> =
+            super::dataset_object_group_pages(client, "ds".to_string(), labels, 2, "".to_string())
+                .try_collect()
+                .await
+                .unwrap();
+
+        // og0002 lacks the label sent to the server, og0001 and og0003 are removed by the
+        // client, the cursor still follows the pages of the server
+        let pages: Vec<Vec<String>> = pages.iter().map(|x| ids(x)).collect();
+        assert_eq!(pages, vec![vec!["og0000"], vec!["og0004"]]);
+        assert_eq!(*cursors.lock().unwrap(), vec!["", "og0001", "og0004"]);
+    }
+}
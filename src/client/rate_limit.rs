@@ -0,0 +1,95 @@
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use bytes::Bytes;
+use futures::{Stream, StreamExt};
+use tokio::sync::Mutex;
+
+/// Size of the chunks in-memory buffers are split into, so that they can be rate limited
+const RATE_LIMIT_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Token bucket that limits the combined rate of all uploads and downloads of the client.
+/// Transfers are not limited if no rate is configured
+#[derive(Clone, Debug)]
+pub struct RateLimiter {
+    bucket: Option<Arc<Mutex<TokenBucket>>>,
+}
+
+#[derive(Debug)]
+struct TokenBucket {
+    bytes_per_sec: f64,
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl RateLimiter {
+    pub fn new(bytes_per_sec: Option<usize>) -> Self {
+        let bucket = bytes_per_sec.filter(|x| *x > 0).map(|bytes_per_sec| {
+            Arc::new(Mutex::new(TokenBucket {
+                bytes_per_sec: bytes_per_sec as f64,
+                tokens: bytes_per_sec as f64,
+                last_refill: Instant::now(),
+            }))
+        });
+
+        return RateLimiter { bucket: bucket };
+    }
+
+    pub fn is_limited(&self) -> bool {
+        self.bucket.is_some()
+    }
+
+    /// Waits until the given number of bytes may be transferred. Chunks larger than the bucket
+    /// are allowed and delay the following transfers instead
+    pub async fn acquire(&self, bytes: usize) {
+        let bucket = match &self.bucket {
+            Some(bucket) => bucket,
+            None => return,
+        };
+
+        // the lock is held while waiting, so that waiting transfers are served in order
+        let mut bucket = bucket.lock().await;
+        let now = Instant::now();
+        let refill = now.duration_since(bucket.last_refill).as_secs_f64() * bucket.bytes_per_sec;
+        // at most one second worth of bytes can be transferred in a burst
+        bucket.tokens = (bucket.tokens + refill).min(bucket.bytes_per_sec);
+        bucket.last_refill = now;
+
+        bucket.tokens -= bytes as f64;
+        if bucket.tokens < 0.0 {
+            let wait = Duration::from_secs_f64(-bucket.tokens / bucket.bytes_per_sec);
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    /// Limits the rate of a stream of chunks, e.g. a request body or a response stream
+    pub fn limit_stream<S, E>(&self, stream: S) -> impl Stream<Item = Result<Bytes, E>>
+    where
+        S: Stream<Item = Result<Bytes, E>>,
+    {
+        let rate_limiter = self.clone();
+        stream.then(move |chunk| {
+            let rate_limiter = rate_limiter.clone();
+            async move {
+                if let Ok(chunk) = &chunk {
+                    rate_limiter.acquire(chunk.len()).await;
+                }
+                chunk
+            }
+        })
+    }
+
+    /// Splits the buffer into chunks that are released according to the rate
+    pub fn limit_bytes(&self, data: Bytes) -> impl Stream<Item = Result<Bytes, std::io::Error>> {
+        let chunks: Vec<Result<Bytes, std::io::Error>> = (0..data.len())
+            .step_by(RATE_LIMIT_CHUNK_SIZE)
+            .map(|start| {
+                Ok(data.slice(start..std::cmp::min(start + RATE_LIMIT_CHUNK_SIZE, data.len())))
+            })
+            .collect();
+
+        self.limit_stream(futures::stream::iter(chunks))
+    }
+}
@@ -0,0 +1,101 @@
+use std::{future::Future, time::Duration};
+
+use rand::Rng;
+use reqwest::StatusCode;
+use tonic::Code;
+
+use crate::util::{
+    config::RetryConfig,
+    error::{CliError, Result},
+};
+
+/// Retries transient failures of gRPC calls and transfers with exponential backoff and full
+/// jitter
+#[derive(Clone, Debug)]
+pub struct RetryPolicy {
+    max_attempts: u32,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    multiplier: f64,
+    retryable_codes: Vec<Code>,
+}
+
+impl RetryPolicy {
+    pub fn from_config(config: &RetryConfig) -> Result<Self> {
+        let retryable_codes = config
+            .retryable_status
+            .iter()
+            .map(|x| parse_code(x))
+            .collect::<Result<Vec<Code>>>()?;
+
+        return Ok(RetryPolicy {
+            max_attempts: std::cmp::max(config.max_attempts, 1),
+            initial_backoff: Duration::from_millis(config.initial_backoff_ms),
+            max_backoff: Duration::from_millis(config.max_backoff_ms),
+            multiplier: config.multiplier,
+            retryable_codes: retryable_codes,
+        });
+    }
+
+    /// Runs the operation until it succeeds, fails with an error that is not retryable or
+    /// the maximum number of attempts is reached. The operation receives the number of the
+    /// current attempt, starting with 0.
+    pub async fn retry<T, F, Fut>(&self, mut operation: F) -> Result<T>
+    where
+        F: FnMut(u32) -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let mut attempt = 0;
+        loop {
+            match operation(attempt).await {
+                Ok(value) => return Ok(value),
+                Err(err) if attempt + 1 < self.max_attempts && self.is_retryable(&err) => {
+                    tokio::time::sleep(self.backoff(attempt)).await;
+                    attempt += 1;
+                }
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
+    fn is_retryable(&self, err: &CliError) -> bool {
+        match err {
+            CliError::Status(status) => self.retryable_codes.contains(&status.code()),
+            CliError::Transport(_) | CliError::IncompleteTransfer(_) => true,
+            CliError::Http(err) => match err.status() {
+                Some(status) => status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS,
+                None => err.is_timeout() || err.is_connect() || err.is_body(),
+            },
+            _ => false,
+        }
+    }
+
+    fn backoff(&self, attempt: u32) -> Duration {
+        let backoff = self
+            .initial_backoff
+            .mul_f64(self.multiplier.powi(attempt as i32))
+            .min(self.max_backoff);
+
+        return backoff.mul_f64(rand::thread_rng().gen_range(0.0..=1.0));
+    }
+}
+
+fn parse_code(code: &str) -> Result<Code> {
+    let code = match code.to_lowercase().replace('-', "_").as_str() {
+        "cancelled" => Code::Cancelled,
+        "unknown" => Code::Unknown,
+        "deadline_exceeded" => Code::DeadlineExceeded,
+        "resource_exhausted" => Code::ResourceExhausted,
+        "aborted" => Code::Aborted,
+        "internal" => Code::Internal,
+        "unavailable" => Code::Unavailable,
+        _ => {
+            return Err(CliError::Config(format!(
+                "{:?} is not a retryable gRPC status",
+                code
+            )))
+        }
+    };
+
+    return Ok(code);
+}
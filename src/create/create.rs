@@ -1,14 +1,35 @@
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
+    io::SeekFrom,
     path::{Path, PathBuf},
+    sync::{Arc, Mutex},
 };
 
-use crate::{client::client, util::cli::CreateRequest};
+use crate::{
+    client::{client, rate_limit::RateLimiter},
+    util::{
+        checksum::{self, ChecksumMismatch},
+        cli::CreateRequest,
+        error::{CliError, Result},
+        progress::{FileProgress, TransferProgress},
+    },
+};
+
+use super::upload_state::{FileFingerprint, UploadKey, UploadState, UploadStateStore};
 
-use reqwest::Body;
+use async_channel::bounded;
+use bytes::Bytes;
+use futures::{
+    future::{try_join, try_join_all},
+    StreamExt, TryStreamExt,
+};
+use reqwest::{header::CONTENT_LENGTH, Body};
 use tokio_util::codec::{BytesCodec, FramedRead};
 
-use tokio::io::AsyncReadExt;
+use tokio::{
+    io::{AsyncReadExt, AsyncSeekExt},
+    sync::OnceCell,
+};
 
 use scienceobjectsdb_rust_api::sciobjectsdb::sciobjsdb::api::storage::{
     models,
@@ -21,15 +42,24 @@ use scienceobjectsdb_rust_api::sciobjectsdb::sciobjsdb::api::storage::{
 };
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
 
-const UPLOAD_BUFFER_SIZE: usize = 5 * 1024 * 1024;
+// S3 compatible storages accept at most 10000 parts of at least 5 MiB per multipart upload
+const MIN_UPLOAD_PART_SIZE: usize = 5 * 1024 * 1024;
+const MAX_UPLOAD_PART_COUNT: usize = 10000;
+const UPLOAD_PART_SIZE_ALIGNMENT: usize = 1024 * 1024;
+const OBJECT_UPLOAD_REQUEST_QUEUE_SIZE: usize = 500;
 
 pub struct Create {
     client: client::Client,
+    /// Opened with the first object upload, the database is locked while it is open
+    upload_state: OnceCell<UploadStateStore>,
+    /// Project of the profile, used for datasets without a project id
+    project_id: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct CreateDataset {
     name: String,
+    #[serde(default)]
     project_id: String,
     description: String,
     labels: Vec<Label>,
@@ -59,7 +89,7 @@ pub struct CreateObjectBatch {
     objects: Vec<CreateObject>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct CreateObject {
     dataset_id: String,
     path: String,
@@ -69,6 +99,25 @@ pub struct CreateObject {
     labels: Vec<Label>,
 }
 
+#[derive(Clone)]
+pub struct ObjectUploadMessage {
+    pub path: String,
+    /// Name of the object group the object is added to after the upload
+    pub object_group: String,
+    pub create_object: CreateObject,
+}
+
+/// A multipart upload of one file whose parts are uploaded at the same time
+#[derive(Clone, Copy)]
+struct MultipartUpload<'a> {
+    path: &'a Path,
+    object_id: &'a str,
+    key: &'a UploadKey,
+    part_size: usize,
+    request: &'a CreateRequest,
+    progress: &'a FileProgress,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Label {
     key: String,
@@ -76,11 +125,22 @@ pub struct Label {
 }
 
 impl Create {
-    pub fn new(client: client::Client) -> Self {
-        return Create { client: client };
+    pub fn new(client: client::Client, project_id: Option<String>) -> Result<Self> {
+        return Ok(Create {
+            client: client,
+            upload_state: OnceCell::new(),
+            project_id: project_id,
+        });
     }
 
-    pub async fn create(&mut self, request: CreateRequest) {
+    pub async fn create(&mut self, request: CreateRequest) -> Result<()> {
+        if request.part_size() < MIN_UPLOAD_PART_SIZE {
+            return Err(CliError::InvalidInput(format!(
+                "part size must be at least {} bytes",
+                MIN_UPLOAD_PART_SIZE
+            )));
+        }
+
         match request.resource {
             crate::util::cli::CreateResource::Dataset => self.create_dataset(request).await,
             crate::util::cli::CreateResource::DatasetVersion => {
@@ -90,7 +150,8 @@ impl Create {
                 self.create_object_group(request, None).await
             }
             crate::util::cli::CreateResource::Object => {
-                self.create_objects(request, None).await;
+                self.create_objects(request, None, "").await?;
+                Ok(())
             }
             crate::util::cli::CreateResource::ObjectGroupFromFile => {
                 self.create_object_groups_from_dir(request).await
@@ -98,8 +159,16 @@ impl Create {
         }
     }
 
-    async fn create_dataset(&mut self, cli_request: CreateRequest) {
-        let request: CreateDataset = self.read_request_file(cli_request.path).await;
+    async fn create_dataset(&mut self, cli_request: CreateRequest) -> Result<()> {
+        let request: CreateDataset = self.read_request_file(cli_request.path).await?;
+        let project_id = match request.project_id.is_empty() {
+            true => self.project_id.clone().ok_or_else(|| {
+                CliError::InvalidInput(
+                    "project_id is required if the profile has no project".to_string(),
+                )
+            })?,
+            false => request.project_id,
+        };
         let labels = request
             .labels
             .into_iter()
@@ -110,20 +179,18 @@ impl Create {
             name: request.name,
             description: request.description,
             labels: labels,
-            project_id: request.project_id,
+            project_id: project_id,
             ..Default::default()
         };
 
-        self.client
-            .dataset_service
-            .create_dataset(dataset)
-            .await
-            .unwrap();
+        self.client.dataset_service.create_dataset(dataset).await?;
+
+        Ok(())
     }
 
-    async fn create_dataset_version(&mut self, request: CreateRequest) {
+    async fn create_dataset_version(&mut self, request: CreateRequest) -> Result<()> {
         let dataset_version_request: CreateDatasetVersion =
-            self.read_request_file(request.path).await;
+            self.read_request_file(request.path).await?;
         let labels = dataset_version_request
             .labels
             .into_iter()
@@ -142,18 +209,19 @@ impl Create {
         self.client
             .dataset_service
             .release_dataset_version(create_dataset_version_request)
-            .await
-            .unwrap();
+            .await?;
+
+        Ok(())
     }
 
     async fn create_object_group(
         &mut self,
         request: CreateRequest,
         create_objects_ff: Option<CreateObjectGroup>,
-    ) {
+    ) -> Result<()> {
         let create_object_group_config = match create_objects_ff {
             Some(create_object_group) => create_object_group,
-            None => self.read_request_file(request.path.clone()).await,
+            None => self.read_request_file(request.path.clone()).await?,
         };
         let labels = create_object_group_config
             .labels
@@ -195,13 +263,14 @@ impl Create {
         self.client
             .dataset_object_service
             .create_object_group(create_object_group_request)
-            .await
-            .unwrap()
-            .into_inner();
+            .await?;
+
+        Ok(())
     }
 
-    async fn create_object_groups_from_dir(&mut self, request: CreateRequest) {
-        let create_og_ff_config: CreateObjectGroup = self.read_request_file(request.path).await;
+    async fn create_object_groups_from_dir(&mut self, request: CreateRequest) -> Result<()> {
+        let create_og_ff_config: CreateObjectGroup =
+            self.read_request_file(request.path.clone()).await?;
 
         let additional_labels: Vec<Label> = create_og_ff_config
             .labels
@@ -212,269 +281,663 @@ impl Create {
                 value: x.value,
             })
             .collect();
-        let origin = create_og_ff_config
-            .path
-            .clone()
-            .expect("No directory specified");
-        let dirs = Path::new(&origin)
-            .read_dir()
-            .expect("Specified directory is not a directory or permissions are not set")
-            .filter(|d| d.as_ref().unwrap().path().is_dir());
+        let origin = create_og_ff_config.path.clone().ok_or_else(|| {
+            CliError::InvalidInput("no directory specified in the object group file".to_string())
+        })?;
+
+        let mut dirs = Vec::new();
+        for entry in Path::new(&origin).read_dir()? {
+            let entry_path = entry?.path();
+            if entry_path.is_dir() {
+                dirs.push(entry_path);
+            }
+        }
 
-        let dirs = dirs.map(|d| d.unwrap().path());
         for group in dirs {
-            let mut path: HashMap<PathBuf, Vec<PathBuf>> =
-                HashMap::from([(PathBuf::from(group), Vec::new())]);
-            let dir = walking_dirs(&mut path);
-            let objects = CreateObjectBatch {
-                objects: dir
-                    .values()
-                    .flatten()
-                    .map(|c| {
-                        let mut labels = additional_labels.clone();
-                        labels.push(Label {
-                            key: "Path".to_string(),
-                            value: c.to_str().unwrap().to_string(),
-                        });
-
-                        CreateObject {
-                            dataset_id: create_og_ff_config.dataset_id.clone(),
-                            // ugly
-                            path: c.canonicalize().unwrap().to_str().unwrap().to_string(),
-                            content_len: c.metadata().unwrap().len() as i64,
-                            filetype: match c.extension() {
-                                Some(c) => c.to_str().unwrap().to_string(),
-                                None => "".to_string(),
-                            },
-                            filename: c.file_name().unwrap().to_str().unwrap().to_string(),
-                            labels: labels.clone(),
-                        }
-                    })
-                    .collect(),
-            };
+            let mut path: HashMap<PathBuf, Vec<PathBuf>> = HashMap::from([(group, Vec::new())]);
+            let dir = walking_dirs(&mut path)?;
+
+            let mut objects = Vec::new();
+            for c in dir.values().flatten() {
+                let mut labels = additional_labels.clone();
+                labels.push(Label {
+                    key: "Path".to_string(),
+                    value: c.to_string_lossy().to_string(),
+                });
+
+                objects.push(CreateObject {
+                    dataset_id: create_og_ff_config.dataset_id.clone(),
+                    path: c.canonicalize()?.to_string_lossy().to_string(),
+                    content_len: c.metadata()?.len() as i64,
+                    filetype: match c.extension() {
+                        Some(c) => c.to_string_lossy().to_string(),
+                        None => "".to_string(),
+                    },
+                    filename: c
+                        .file_name()
+                        .unwrap_or_default()
+                        .to_string_lossy()
+                        .to_string(),
+                    labels: labels,
+                });
+            }
+            let objects = CreateObjectBatch { objects: objects };
+
             // create objects
             let create_request = CreateRequest {
                 resource: crate::util::cli::CreateResource::Object,
                 path: " ".to_string(),
+                resume: request.resume,
+                parallel_parts: request.parallel_parts,
+                multipart_threshold: request.multipart_threshold,
+                part_size: request.part_size,
+                workers: request.workers,
+                verify_etag: request.verify_etag,
             };
-            let ids = self.create_objects(create_request, Some(objects)).await;
+            let ids = self
+                .create_objects(create_request, Some(objects), &create_og_ff_config.name)
+                .await?;
 
             // create object groups for the first level and add ids
             let create_request_2 = CreateRequest {
                 resource: crate::util::cli::CreateResource::ObjectGroup,
                 path: " ".to_string(),
+                resume: request.resume,
+                parallel_parts: request.parallel_parts,
+                multipart_threshold: request.multipart_threshold,
+                part_size: request.part_size,
+                workers: request.workers,
+                verify_etag: request.verify_etag,
             };
 
             let mut create_og_ff_groups = create_og_ff_config.clone();
             create_og_ff_groups.objects_ids = Some(ids);
 
             self.create_object_group(create_request_2, Some(create_og_ff_groups))
-                .await;
+                .await?;
         }
+
+        Ok(())
     }
 
     async fn create_objects(
-        &mut self,
+        &self,
         request: CreateRequest,
         from_dir: Option<CreateObjectBatch>,
-    ) -> Vec<String> {
+        object_group: &str,
+    ) -> Result<Vec<String>> {
         let create_object_batch_config: CreateObjectBatch = match from_dir {
             Some(request) => request,
-            None => self.read_request_file(request.path.clone()).await,
+            None => self.read_request_file(request.path.clone()).await?,
         };
-        let mut object_map = HashMap::new();
 
-        for object in &create_object_batch_config.objects {
-            let create_object_request = self.create_object_from_file(object).await;
-            object_map.insert(object.path.to_owned(), create_object_request);
-        }
+        let (sender, recv) = bounded(OBJECT_UPLOAD_REQUEST_QUEUE_SIZE);
+        let progress = TransferProgress::new("Uploaded");
 
-        let mut ids = Vec::new();
+        let worker_future = self.start_upload_worker(&request, &progress, recv);
+        let object_future = async move {
+            let mut queued_paths = HashSet::new();
+            for object in &create_object_batch_config.objects {
+                // the same file is only uploaded once
+                if !queued_paths.insert(object.path.clone()) {
+                    continue;
+                }
 
-        for (path, request) in object_map {
-            let create_objects_response = self
-                .client
-                .dataset_object_service
-                .create_object(request)
-                .await
-                .unwrap()
-                .into_inner();
-            let as_path = Path::new(path.as_str());
-            let file = tokio::fs::File::open(as_path).await.unwrap();
+                let msg = ObjectUploadMessage {
+                    path: object.path.clone(),
+                    object_group: object_group.to_string(),
+                    create_object: object.clone(),
+                };
+                sender.send(msg).await?;
+            }
 
-            let size = file.metadata().await.unwrap().len() as usize;
+            Ok(create_object_batch_config)
+        };
 
-            ids.push(create_objects_response.id.clone());
+        let result = try_join(worker_future, object_future).await;
+        progress.finish();
 
-            if size < UPLOAD_BUFFER_SIZE {
-                self.upload_file(path.clone(), create_objects_response.id)
-                    .await
-            } else {
-                self.upload_file_multipart(path.clone(), create_objects_response.id)
-                    .await;
-            }
-        }
-        ids
+        let (object_ids, create_object_batch_config) = result?;
+
+        let ids = create_object_batch_config
+            .objects
+            .iter()
+            .filter_map(|object| object_ids.get(&object.path).cloned())
+            .collect();
+
+        Ok(ids)
     }
 
-    async fn upload_file_multipart(&mut self, path: String, object_id: String) {
-        self.client
-            .object_load_service
-            .start_multipart_upload(StartMultipartUploadRequest {
-                id: object_id.clone(),
-            })
-            .await
-            .unwrap()
-            .into_inner();
+    async fn start_upload_worker(
+        &self,
+        request: &CreateRequest,
+        progress: &TransferProgress,
+        recv: async_channel::Receiver<ObjectUploadMessage>,
+    ) -> Result<HashMap<String, String>> {
+        let mut worker = Vec::new();
 
-        let path = Path::new(path.as_str());
-        let mut file = tokio::fs::File::open(path).await.unwrap();
-        let mut remaining_bytes: usize = file.metadata().await.unwrap().len() as usize;
+        for _ in 0..request.workers() {
+            worker.push(self.upload_object_loop(request, progress, recv.clone()));
+        }
 
-        let mut upload_part_counter: i64 = 0;
-        let mut etags: Vec<CompletedParts> = Vec::new();
+        let mut object_ids = HashMap::new();
+        for worker_object_ids in try_join_all(worker).await? {
+            object_ids.extend(worker_object_ids);
+        }
+
+        Ok(object_ids)
+    }
 
-        loop {
-            upload_part_counter = upload_part_counter + 1;
+    /// Uploads the received objects and returns the ids of the created objects by path
+    async fn upload_object_loop(
+        &self,
+        request: &CreateRequest,
+        progress: &TransferProgress,
+        mut recv: async_channel::Receiver<ObjectUploadMessage>,
+    ) -> Result<HashMap<String, String>> {
+        let mut object_ids = HashMap::new();
+
+        while let Some(object_msg) = recv.next().await {
+            let key = UploadKey {
+                dataset_id: object_msg.create_object.dataset_id.clone(),
+                object_group: object_msg.object_group.clone(),
+                fingerprint: FileFingerprint::from_path(Path::new(object_msg.path.as_str()))
+                    .await?,
+            };
 
-            let mut buffer_size = UPLOAD_BUFFER_SIZE;
+            // uploads of the same file into the same object group that were interrupted in a
+            // previous run are continued
+            let resumable_state = match request.resume {
+                true => self.upload_state().await?.get(&key)?,
+                false => None,
+            };
 
-            if remaining_bytes < UPLOAD_BUFFER_SIZE {
-                buffer_size = remaining_bytes;
+            let file_name = Path::new(object_msg.path.as_str())
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .to_string();
+            let file_progress = progress.start_file(file_name, key.fingerprint.size);
+            let result = async {
+                match resumable_state {
+                    Some(state) => {
+                        let object_id = state.object_id.clone();
+                        self.upload_object(
+                            object_msg.path.clone(),
+                            object_id.clone(),
+                            key,
+                            Some(state),
+                            request,
+                            &file_progress,
+                        )
+                        .await?;
+                        Ok(object_id)
+                    }
+                    None => {
+                        let create_object_request = self
+                            .create_object_from_file(&object_msg.create_object)
+                            .await?;
+                        let create_objects_response = self
+                            .client
+                            .dataset_object_service
+                            .clone()
+                            .create_object(create_object_request)
+                            .await?
+                            .into_inner();
+
+                        self.upload_object(
+                            object_msg.path.clone(),
+                            create_objects_response.id.clone(),
+                            key,
+                            None,
+                            request,
+                            &file_progress,
+                        )
+                        .await?;
+                        Ok(create_objects_response.id)
+                    }
+                }
             }
+            .await;
+            let object_id = match result {
+                Ok(object_id) => object_id,
+                Err(err) => {
+                    file_progress.fail();
+                    return Err(err);
+                }
+            };
+            file_progress.finish();
 
-            let mut data_buf = vec![0u8; buffer_size];
-            file.read_buf(&mut data_buf).await.unwrap();
+            object_ids.insert(object_msg.path, object_id);
+        }
 
-            let upload_link = self
-                .client
-                .object_load_service
-                .get_multipart_upload_link(GetMultipartUploadLinkRequest {
-                    object_id: object_id.clone(),
-                    upload_part: upload_part_counter,
-                })
-                .await
-                .unwrap()
-                .into_inner();
+        Ok(object_ids)
+    }
 
-            let etag = self.upload_part(upload_link.upload_link, data_buf).await;
-            etags.push(CompletedParts {
-                etag: etag,
-                part: upload_part_counter,
-            });
+    async fn upload_object(
+        &self,
+        path: String,
+        object_id: String,
+        key: UploadKey,
+        state: Option<UploadState>,
+        request: &CreateRequest,
+        progress: &FileProgress,
+    ) -> Result<()> {
+        let file_size = key.fingerprint.size as usize;
+        // empty files can not be uploaded in parts
+        if file_size < request.multipart_threshold() || file_size == 0 {
+            self.upload_file(path, object_id, request.verify_etag, progress)
+                .await
+        } else {
+            self.upload_file_multipart(path, object_id, &key, state, request, progress)
+                .await
+        }
+    }
 
-            remaining_bytes = remaining_bytes - buffer_size;
+    async fn upload_file_multipart(
+        &self,
+        path: String,
+        object_id: String,
+        key: &UploadKey,
+        state: Option<UploadState>,
+        request: &CreateRequest,
+        progress: &FileProgress,
+    ) -> Result<()> {
+        let (part_size, completed_parts) = match state {
+            Some(state) => (state.part_size, state.completed_parts),
+            None => {
+                self.client
+                    .object_load_service
+                    .clone()
+                    .start_multipart_upload(StartMultipartUploadRequest {
+                        id: object_id.clone(),
+                    })
+                    .await?;
+
+                // only multipart uploads can be continued, their parts are recorded if the
+                // upload may be resumed by a later run
+                let part_size =
+                    upload_part_size(key.fingerprint.size as usize, request.part_size());
+                if request.resume {
+                    self.upload_state()
+                        .await?
+                        .start(object_id.clone(), key.clone(), part_size)
+                        .await?;
+                }
 
-            if remaining_bytes == 0 {
-                break;
+                (part_size, Vec::new())
             }
-        }
+        };
 
+        let path = Path::new(path.as_str());
+        let file_size = key.fingerprint.size as usize;
+        let part_count = file_size.div_ceil(part_size);
+
+        let confirmed_parts: HashSet<i64> = completed_parts.iter().map(|x| x.part).collect();
+        progress.set_position(
+            confirmed_parts
+                .iter()
+                .map(|x| part_len(file_size, part_size, *x) as u64)
+                .sum(),
+        );
+        let mut etags: Vec<CompletedParts> = completed_parts
+            .into_iter()
+            .map(|x| CompletedParts {
+                etag: x.etag,
+                part: x.part,
+            })
+            .collect();
+
+        let pending_parts: Vec<i64> = (1..=part_count as i64)
+            .filter(|x| !confirmed_parts.contains(x))
+            .collect();
+
+        let upload = MultipartUpload {
+            path: path,
+            object_id: &object_id,
+            key: key,
+            part_size: part_size,
+            request: request,
+            progress: progress,
+        };
+
+        // at most parallel_parts parts are held in memory and uploaded at the same time
+        let mut uploaded_parts: Vec<CompletedParts> = futures::stream::iter(pending_parts)
+            .map(|upload_part_counter| self.upload_multipart_part(&upload, upload_part_counter))
+            .buffer_unordered(request.parallel_parts())
+            .try_collect()
+            .await?;
+
+        etags.append(&mut uploaded_parts);
+        etags.sort_by_key(|x| x.part);
+
+        // not retried, a retry of a completion that succeeded on the server would fail
         self.client
             .object_load_service
+            .clone()
             .complete_multipart_upload(CompleteMultipartUploadRequest {
                 object_id: object_id.clone(),
                 parts: etags,
             })
-            .await
-            .unwrap();
+            .await?;
+
+        // the object is complete, a later run creates a new object for the file
+        if request.resume {
+            self.upload_state().await?.remove(key).await?;
+        }
+
+        Ok(())
     }
 
-    async fn upload_file(&mut self, path: String, object_id: String) {
+    async fn upload_multipart_part(
+        &self,
+        upload: &MultipartUpload<'_>,
+        upload_part_counter: i64,
+    ) -> Result<CompletedParts> {
+        let MultipartUpload {
+            path,
+            object_id,
+            key,
+            part_size,
+            request,
+            progress,
+        } = *upload;
+        let offset = (upload_part_counter as usize - 1) * part_size;
+        let buffer_size = part_len(
+            key.fingerprint.size as usize,
+            part_size,
+            upload_part_counter,
+        );
+
+        let mut data_buf = vec![0u8; buffer_size];
+        let mut file = tokio::fs::File::open(path).await?;
+        file.seek(SeekFrom::Start(offset as u64)).await?;
+        file.read_exact(&mut data_buf).await?;
+
         let upload_link = self
             .client
-            .object_load_service
-            .create_upload_link(CreateUploadLinkRequest { id: object_id })
-            .await
-            .unwrap()
-            .into_inner();
+            .call(
+                &self.client.object_load_service,
+                GetMultipartUploadLinkRequest {
+                    object_id: object_id.to_string(),
+                    upload_part: upload_part_counter,
+                },
+                |mut service, req| async move { service.get_multipart_upload_link(req).await },
+            )
+            .await?;
+
+        let md5 = format!("{:x}", md5::compute(&data_buf));
+        let etag = self.upload_part(upload_link.upload_link, data_buf).await?;
+        if request.verify_etag && !checksum::etag_matches_md5(&etag, &md5) {
+            return Err(CliError::ChecksumMismatch(vec![ChecksumMismatch {
+                path: format!("{} (part {})", path.display(), upload_part_counter),
+                expected: md5,
+                actual: etag,
+            }]));
+        }
+
+        if request.resume {
+            self.upload_state()
+                .await?
+                .add_part(key, upload_part_counter, etag.clone())
+                .await?;
+        }
+        progress.inc(buffer_size as u64);
+
+        return Ok(CompletedParts {
+            etag: etag,
+            part: upload_part_counter,
+        });
+    }
+
+    async fn upload_file(
+        &self,
+        path: String,
+        object_id: String,
+        verify_etag: bool,
+        progress: &FileProgress,
+    ) -> Result<()> {
+        let upload_link = self
+            .client
+            .call(
+                &self.client.object_load_service,
+                CreateUploadLinkRequest { id: object_id },
+                |mut service, req| async move { service.create_upload_link(req).await },
+            )
+            .await?;
 
         let path = Path::new(path.as_str());
-        let file = tokio::fs::File::open(path).await.unwrap();
+        let (etag, md5) = self
+            .client
+            .retry_policy
+            .retry(|_| {
+                Create::put_file(
+                    upload_link.upload_link.clone(),
+                    path,
+                    progress,
+                    &self.client.rate_limiter,
+                )
+            })
+            .await?;
+
+        if verify_etag {
+            let etag = etag.unwrap_or_default();
+            if !checksum::etag_matches_md5(&etag, &md5) {
+                return Err(CliError::ChecksumMismatch(vec![ChecksumMismatch {
+                    path: path.display().to_string(),
+                    expected: md5,
+                    actual: etag,
+                }]));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Uploads the file and returns the ETag of the response and the MD5 of the data that was
+    /// sent
+    async fn put_file(
+        upload_link: String,
+        path: &Path,
+        progress: &FileProgress,
+        rate_limiter: &RateLimiter,
+    ) -> Result<(Option<String>, String)> {
+        let file = tokio::fs::File::open(path).await?;
+        progress.set_position(0);
 
         let client = reqwest::Client::new();
 
-        let stream = FramedRead::new(file, BytesCodec::new());
+        let md5_context = Arc::new(Mutex::new(md5::Context::new()));
+        let stream_md5_context = md5_context.clone();
+        let stream_progress = progress.clone();
+        let stream = FramedRead::new(file, BytesCodec::new()).map_ok(|x| x.freeze());
+        let stream = rate_limiter.limit_stream(stream).inspect_ok(move |chunk| {
+            stream_progress.inc(chunk.len() as u64);
+            if let Ok(mut context) = stream_md5_context.lock() {
+                context.consume(chunk);
+            }
+        });
         let body = Body::wrap_stream(stream);
 
-        client
-            .put(upload_link.upload_link)
+        let response = client
+            .put(upload_link)
             .body(body)
             .send()
+            .await?
+            .error_for_status()?;
+        let etag = response
+            .headers()
+            .get("ETag")
+            .and_then(|x| x.to_str().ok())
+            .map(|x| x.to_string());
+
+        let md5 = match md5_context.lock() {
+            Ok(mut context) => {
+                let digest = std::mem::replace(&mut *context, md5::Context::new()).compute();
+                format!("{:x}", digest)
+            }
+            Err(_) => {
+                return Err(CliError::Internal(
+                    "failed to compute the MD5 of the uploaded data".to_string(),
+                ))
+            }
+        };
+
+        return Ok((etag, md5));
+    }
+
+    async fn upload_part(&self, upload_link: String, data_buf: Vec<u8>) -> Result<String> {
+        let data_buf = Bytes::from(data_buf);
+        self.client
+            .retry_policy
+            .retry(|_| {
+                Create::put_part(
+                    upload_link.clone(),
+                    data_buf.clone(),
+                    &self.client.rate_limiter,
+                )
+            })
             .await
-            .unwrap();
     }
 
-    async fn upload_part(&mut self, upload_link: String, data_buf: Vec<u8>) -> String {
+    async fn put_part(
+        upload_link: String,
+        data_buf: Bytes,
+        rate_limiter: &RateLimiter,
+    ) -> Result<String> {
         let client = reqwest::Client::new();
-        let response = client.put(upload_link).body(data_buf).send().await.unwrap();
-        let etag_raw = response.headers().get("ETag").unwrap().as_bytes();
-        let etag = std::str::from_utf8(etag_raw).unwrap().to_string();
+        let content_len = data_buf.len();
+        let body = match rate_limiter.is_limited() {
+            true => Body::wrap_stream(rate_limiter.limit_bytes(data_buf)),
+            false => Body::from(data_buf),
+        };
 
-        return etag;
+        let response = client
+            .put(upload_link)
+            .header(CONTENT_LENGTH, content_len)
+            .body(body)
+            .send()
+            .await?
+            .error_for_status()?;
+        let etag = response
+            .headers()
+            .get("ETag")
+            .and_then(|x| x.to_str().ok())
+            .ok_or_else(|| {
+                CliError::InvalidResponse("upload response did not contain an ETag".to_string())
+            })?
+            .to_string();
+
+        return Ok(etag);
     }
 
-    async fn create_object_from_file(&self, create_object: &CreateObject) -> CreateObjectRequest {
+    async fn create_object_from_file(
+        &self,
+        create_object: &CreateObject,
+    ) -> Result<CreateObjectRequest> {
         let path = Path::new(create_object.path.as_str());
-        let file = tokio::fs::File::open(path).await.unwrap();
-        let filename = path.file_stem().unwrap().to_str().unwrap().to_string();
+        let file = tokio::fs::File::open(path).await?;
+        let filename = path
+            .file_stem()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string();
         let file_extension = match path.extension() {
-            Some(value) => value.to_str().unwrap().to_string(),
+            Some(value) => value.to_string_lossy().to_string(),
             None => "".to_string(),
         };
 
-        let labels = create_object
+        let mut labels: Vec<models::v1::Label> = create_object
             .labels
             .iter()
             .map(|x| x.to_proto_label())
             .collect();
+        // the checksum is stored with the object so that downloads can be verified. Labels of
+        // objects can only be set when the object is created, which has to happen before the
+        // upload link is issued, so the file is hashed in a separate pass before the upload.
+        // Resumed uploads reuse the existing object and are not hashed again
+        if checksum::sha256_from_labels(&labels).is_none() {
+            labels.push(models::v1::Label {
+                key: checksum::SHA256_LABEL_KEY.to_string(),
+                value: checksum::sha256_file(path).await?,
+            });
+        }
+
         let create_object_request = CreateObjectRequest {
             dataset_id: create_object.dataset_id.clone(),
-            content_len: file.metadata().await.unwrap().len() as i64,
+            content_len: file.metadata().await?.len() as i64,
             filename: filename,
             filetype: file_extension,
             labels: labels,
             ..Default::default()
         };
 
-        return create_object_request;
+        return Ok(create_object_request);
+    }
+
+    async fn upload_state(&self) -> Result<&UploadStateStore> {
+        self.upload_state
+            .get_or_try_init(|| async { UploadStateStore::new() })
+            .await
     }
 
-    async fn read_request_file<Z: DeserializeOwned>(&self, file_path: String) -> Z {
-        let data = tokio::fs::read_to_string(file_path).await.unwrap();
-        let create_request: Z = serde_yaml::from_str(data.as_str()).unwrap();
+    async fn read_request_file<Z: DeserializeOwned>(&self, file_path: String) -> Result<Z> {
+        let data = tokio::fs::read_to_string(file_path).await?;
+        let create_request: Z = serde_yaml::from_str(data.as_str())?;
+
+        return Ok(create_request);
+    }
+}
+/// Length of the part with the given number, the last part of a file may be shorter
+fn part_len(file_size: usize, part_size: usize, part: i64) -> usize {
+    let offset = (part as usize - 1) * part_size;
+    std::cmp::min(part_size, file_size - offset)
+}
 
-        return create_request;
+/// Grows the configured part size for large files, so that the file fits into the maximum
+/// number of parts of a multipart upload
+fn upload_part_size(file_size: usize, part_size: usize) -> usize {
+    let min_part_size = file_size.div_ceil(MAX_UPLOAD_PART_COUNT);
+    if min_part_size <= part_size {
+        return part_size;
     }
+
+    let aligned_part_size =
+        min_part_size.div_ceil(UPLOAD_PART_SIZE_ALIGNMENT) * UPLOAD_PART_SIZE_ALIGNMENT;
+
+    return aligned_part_size;
 }
+
 fn walking_dirs(
     entries: &mut HashMap<PathBuf, Vec<PathBuf>>,
-) -> &mut HashMap<PathBuf, Vec<PathBuf>> {
+) -> Result<&mut HashMap<PathBuf, Vec<PathBuf>>> {
     // not efficient because whole map is cloned instead of only keys
     let dirs = &mut entries.clone();
     for dir in dirs.keys() {
         let mut files = Vec::new();
-        for entry in dir.read_dir().expect("read_dir call failed").flatten() {
+        for entry in dir.read_dir()?.flatten() {
             if entry.path().is_dir() {
                 let mut temp_map = HashMap::from([(entry.path().to_path_buf(), Vec::new())]);
-                let rec_dirs = walking_dirs(&mut temp_map);
+                let rec_dirs = walking_dirs(&mut temp_map)?;
                 entries.extend(
                     rec_dirs
-                        .keys()
+                        .iter()
                         // too much cloning here, not sure if neccessary
-                        .map(|k| (k.clone(), rec_dirs.get(k).unwrap().clone())),
+                        .map(|(k, v)| (k.clone(), v.clone())),
                 );
             } else if entry.path().is_file() {
                 files.push(entry.path().to_path_buf());
             } else {
-                // maybe panic is the right call because user should be notified that
-                // not everything is uploaded as expected,
-                panic!("Not sure how to deal with symlinks or permission errors for now");
+                // the user should be notified that not everything is uploaded as expected
+                return Err(CliError::InvalidInput(format!(
+                    "can not upload {:?}, symlinks and files without read permission are not supported",
+                    entry.path()
+                )));
             };
         }
         entries.insert(dir.to_path_buf(), files);
     }
-    entries
+    Ok(entries)
 }
 impl Label {
     fn to_proto_label(&self) -> models::v1::Label {
@@ -0,0 +1,168 @@
+use std::{
+    path::{Path, PathBuf},
+    time::UNIX_EPOCH,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::util::error::{CliError, Result};
+
+const UPLOAD_STATE_DB_PATH: &str = ".sciobjsdb/upload_state";
+
+/// Identifies a local file at the time its upload was started. A resumed upload is only
+/// continued if the file still matches the stored fingerprint.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct FileFingerprint {
+    pub path: String,
+    pub size: u64,
+    pub modified: u64,
+}
+
+/// Identifies an upload. The same file uploaded into another dataset or object group is a
+/// separate upload with its own object
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct UploadKey {
+    pub dataset_id: String,
+    /// Object groups are created after their objects were uploaded, so they are identified by
+    /// name, empty for objects that are not added to an object group
+    pub object_group: String,
+    pub fingerprint: FileFingerprint,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CompletedPart {
+    pub part: i64,
+    pub etag: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct UploadState {
+    pub object_id: String,
+    pub key: UploadKey,
+    pub part_size: usize,
+    pub completed_parts: Vec<CompletedPart>,
+}
+
+/// Persists the state of running uploads in a local sled database, so that interrupted
+/// uploads can be continued with `create --resume`. The state of an upload is removed once
+/// it is complete
+#[derive(Clone)]
+pub struct UploadStateStore {
+    db: sled::Db,
+}
+
+impl FileFingerprint {
+    pub async fn from_path(path: &Path) -> Result<Self> {
+        let canonical_path = path.canonicalize()?;
+        let metadata = tokio::fs::metadata(&canonical_path).await?;
+        let modified = metadata
+            .modified()?
+            .duration_since(UNIX_EPOCH)
+            .map(|x| x.as_secs())
+            .unwrap_or_default();
+
+        return Ok(FileFingerprint {
+            path: canonical_path.to_string_lossy().to_string(),
+            size: metadata.len(),
+            modified: modified,
+        });
+    }
+}
+
+impl UploadKey {
+    fn db_key(&self) -> Vec<u8> {
+        format!(
+            "{}\n{}\n{}",
+            self.dataset_id, self.object_group, self.fingerprint.path
+        )
+        .into_bytes()
+    }
+}
+
+impl UploadStateStore {
+    pub fn new() -> Result<Self> {
+        let dirs = directories::UserDirs::new()
+            .ok_or_else(|| CliError::Config("could not determine home directory".to_string()))?;
+        let mut path_buf = PathBuf::new();
+        path_buf.push(dirs.home_dir());
+        path_buf.push(UPLOAD_STATE_DB_PATH);
+
+        return UploadStateStore::specified_path(path_buf.as_path());
+    }
+
+    pub fn specified_path(path: &Path) -> Result<Self> {
+        let db = sled::open(path)?;
+
+        return Ok(UploadStateStore { db: db });
+    }
+
+    /// Returns the stored state of the given upload, if the file has not changed since the
+    /// upload was started
+    pub fn get(&self, key: &UploadKey) -> Result<Option<UploadState>> {
+        let value = match self.db.get(key.db_key())? {
+            Some(value) => value,
+            None => return Ok(None),
+        };
+        let state: UploadState = serde_yaml::from_slice(&value)?;
+
+        if &state.key != key {
+            return Ok(None);
+        }
+
+        return Ok(Some(state));
+    }
+
+    pub async fn start(&self, object_id: String, key: UploadKey, part_size: usize) -> Result<()> {
+        let state = UploadState {
+            object_id: object_id,
+            key: key,
+            part_size: part_size,
+            completed_parts: Vec::new(),
+        };
+
+        self.put(&state).await
+    }
+
+    pub async fn add_part(&self, key: &UploadKey, part: i64, etag: String) -> Result<()> {
+        // parts can be confirmed concurrently, so the state is updated atomically
+        let mut update_result = Ok(());
+        self.db.fetch_and_update(key.db_key(), |value| {
+            let value = value?;
+            let updated = serde_yaml::from_slice::<UploadState>(value).and_then(|mut state| {
+                state.completed_parts.push(CompletedPart {
+                    part: part,
+                    etag: etag.clone(),
+                });
+                serde_yaml::to_vec(&state)
+            });
+
+            match updated {
+                Ok(updated) => Some(updated),
+                Err(err) => {
+                    update_result = Err(err);
+                    Some(value.to_vec())
+                }
+            }
+        })?;
+        update_result?;
+        self.db.flush_async().await?;
+
+        Ok(())
+    }
+
+    /// Removes the state of a completed upload
+    pub async fn remove(&self, key: &UploadKey) -> Result<()> {
+        self.db.remove(key.db_key())?;
+        self.db.flush_async().await?;
+
+        Ok(())
+    }
+
+    async fn put(&self, state: &UploadState) -> Result<()> {
+        let value = serde_yaml::to_vec(state)?;
+        self.db.insert(state.key.db_key(), value)?;
+        self.db.flush_async().await?;
+
+        Ok(())
+    }
+}
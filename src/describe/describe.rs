@@ -1,69 +1,120 @@
 use scienceobjectsdb_rust_api::sciobjectsdb::sciobjsdb::api::storage::services::v1::{
-    GetDatasetRequest, GetDatasetVersionRequest, GetObjectGroupRequest, GetProjectRequest,
+    CreateDownloadLinkRequest, GetDatasetRequest, GetDatasetVersionRequest, GetObjectGroupRequest,
+    GetProjectRequest,
 };
 
 use crate::client::client;
+use crate::output::output::OutputWriter;
+use crate::output::views::{
+    DatasetVersionView, DatasetView, ObjectGroupView, ObjectView, ProjectView,
+};
 use crate::util::cli;
+use crate::util::error::{CliError, Result};
 
 pub struct Describe {
     client: client::Client,
+    output: OutputWriter,
 }
 
 impl Describe {
-    pub fn new(client: client::Client) -> Self {
-        return Describe { client: client };
+    pub fn new(client: client::Client, output: OutputWriter) -> Self {
+        return Describe {
+            client: client,
+            output: output,
+        };
     }
 
-    pub async fn describe(&mut self, request: cli::Describe) {
+    pub async fn describe(&mut self, request: cli::Describe) -> Result<()> {
+        let id = cli::required_id(&request.id)?;
+
         match request.resource {
             cli::Resource::Project => {
                 let project = self
                     .client
-                    .project_service
-                    .get_project(GetProjectRequest { id: request.id })
-                    .await
-                    .unwrap()
-                    .into_inner();
+                    .call(
+                        &self.client.project_service,
+                        GetProjectRequest { id: id.clone() },
+                        |mut service, req| async move { service.get_project(req).await },
+                    )
+                    .await?;
 
-                println!("{:#?}", project)
+                let project = project.project.ok_or_else(|| {
+                    CliError::InvalidResponse("project response was empty".to_string())
+                })?;
+                self.output.write_single(&ProjectView::from(&project))?
             }
             cli::Resource::Dataset => {
                 let dataset = self
                     .client
-                    .dataset_service
-                    .get_dataset(GetDatasetRequest { id: request.id })
-                    .await
-                    .unwrap()
-                    .into_inner();
+                    .call(
+                        &self.client.dataset_service,
+                        GetDatasetRequest { id: id.clone() },
+                        |mut service, req| async move { service.get_dataset(req).await },
+                    )
+                    .await?;
 
-                println!("{:#?}", dataset)
+                let dataset = dataset.dataset.ok_or_else(|| {
+                    CliError::InvalidResponse("dataset response was empty".to_string())
+                })?;
+                self.output.write_single(&DatasetView::from(&dataset))?
             }
             cli::Resource::DatasetVersion => {
                 let dataset_version = self
                     .client
-                    .dataset_service
-                    .get_dataset_version(GetDatasetVersionRequest { id: request.id })
-                    .await
-                    .unwrap()
-                    .into_inner();
+                    .call(
+                        &self.client.dataset_service,
+                        GetDatasetVersionRequest { id: id.clone() },
+                        |mut service, req| async move { service.get_dataset_version(req).await },
+                    )
+                    .await?;
 
-                println!("{:#?}", dataset_version)
+                let dataset_version = dataset_version.dataset_version.ok_or_else(|| {
+                    CliError::InvalidResponse("dataset version response was empty".to_string())
+                })?;
+                self.output
+                    .write_single(&DatasetVersionView::from(&dataset_version))?
             }
             cli::Resource::ObjectGroup => {
                 let object_group = self
                     .client
-                    .dataset_object_service
-                    .get_object_group(GetObjectGroupRequest {
-                        id: request.id,
-                        // left empty to show all revisions
-                        pagination: None,
-                    })
-                    .await
-                    .unwrap()
-                    .into_inner();
+                    .call(
+                        &self.client.dataset_object_service,
+                        GetObjectGroupRequest {
+                            id: id.clone(),
+                            // left empty to show all revisions
+                            pagination: None,
+                        },
+                        |mut service, req| async move { service.get_object_group(req).await },
+                    )
+                    .await?;
+
+                let object_group = object_group.object_group.ok_or_else(|| {
+                    CliError::InvalidResponse("object group response was empty".to_string())
+                })?;
+                self.output
+                    .write_single(&ObjectGroupView::from(&object_group))?
+            }
+            cli::Resource::Object => {
+                // the object is returned together with a download link
+                let object = self
+                    .client
+                    .call(
+                        &self.client.object_load_service,
+                        CreateDownloadLinkRequest {
+                            id: id.clone(),
+                            ..Default::default()
+                        },
+                        |mut service, req| async move { service.create_download_link(req).await },
+                    )
+                    .await?;
 
-                println!("{:#?}", object_group)
+                let object = object.object.ok_or_else(|| {
+                    CliError::InvalidResponse("object response was empty".to_string())
+                })?;
+                self.output.write_single(&ObjectView::from(&object))?
             }
         }
+
+        Ok(())
     }
 }
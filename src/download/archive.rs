@@ -0,0 +1,256 @@
+use std::{
+    fs::File,
+    io::{Read, Write},
+    path::{Path, PathBuf},
+};
+
+use bytes::{Buf, Bytes};
+use flate2::{write::GzEncoder, Compression};
+use tokio::{sync::mpsc, task::JoinHandle};
+use zip::{write::FileOptions, CompressionMethod, ZipWriter};
+
+use crate::util::error::{CliError, Result};
+
+const ARCHIVE_ENTRY_QUEUE_SIZE: usize = 1;
+const ARCHIVE_CHUNK_QUEUE_SIZE: usize = 16;
+const ARCHIVE_FILE_MODE: u32 = 0o644;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ArchiveFormat {
+    Tar,
+    TarGz,
+    Zip,
+}
+
+impl ArchiveFormat {
+    /// Detects the format from the file extension of the archive
+    pub fn from_path(path: &Path) -> Result<Self> {
+        let file_name = path
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_lowercase();
+
+        if file_name.ends_with(".tar.gz") || file_name.ends_with(".tgz") {
+            return Ok(ArchiveFormat::TarGz);
+        }
+        if file_name.ends_with(".tar") {
+            return Ok(ArchiveFormat::Tar);
+        }
+        if file_name.ends_with(".zip") {
+            return Ok(ArchiveFormat::Zip);
+        }
+
+        return Err(CliError::InvalidInput(format!(
+            "unsupported archive {:?}, expected a .tar, .tar.gz, .tgz or .zip file",
+            path
+        )));
+    }
+}
+
+struct ArchiveEntry {
+    path: String,
+    len: u64,
+    chunks: mpsc::Receiver<Bytes>,
+}
+
+/// Writes the downloaded objects one after another into an archive. The archive is written by
+/// a blocking task that reads the data of the current entry from a channel
+pub struct ArchiveWriter {
+    path: PathBuf,
+    entries: Option<mpsc::Sender<ArchiveEntry>>,
+    handle: Option<JoinHandle<Result<()>>>,
+}
+
+impl ArchiveWriter {
+    pub fn create(path: &Path) -> Result<Self> {
+        let format = ArchiveFormat::from_path(path)?;
+        let file = File::create(path)?;
+        let (sender, recv) = mpsc::channel(ARCHIVE_ENTRY_QUEUE_SIZE);
+
+        let handle = tokio::task::spawn_blocking(move || match format {
+            ArchiveFormat::Tar => {
+                let file = write_tar(tar::Builder::new(file), recv)?;
+                Ok(file.sync_all()?)
+            }
+            ArchiveFormat::TarGz => {
+                let encoder = write_tar(
+                    tar::Builder::new(GzEncoder::new(file, Compression::default())),
+                    recv,
+                )?;
+                Ok(encoder.finish()?.sync_all()?)
+            }
+            ArchiveFormat::Zip => write_zip(ZipWriter::new(file), recv),
+        });
+
+        return Ok(ArchiveWriter {
+            path: path.to_path_buf(),
+            entries: Some(sender),
+            handle: Some(handle),
+        });
+    }
+
+    /// Starts a new entry, the returned sender receives exactly `len` bytes of data. The entry
+    /// is complete once the sender was dropped
+    pub async fn add_entry(&mut self, path: String, len: u64) -> Result<mpsc::Sender<Bytes>> {
+        let (sender, recv) = mpsc::channel(ARCHIVE_CHUNK_QUEUE_SIZE);
+        let entry = ArchiveEntry {
+            path: path,
+            len: len,
+            chunks: recv,
+        };
+
+        let entries = match &self.entries {
+            Some(entries) => entries,
+            None => {
+                return Err(CliError::Internal(
+                    "the archive was already closed".to_string(),
+                ))
+            }
+        };
+        if entries.send(entry).await.is_err() {
+            return Err(self.failure().await);
+        }
+
+        return Ok(sender);
+    }
+
+    /// The error that stopped the archive task, should only be called once a channel to the
+    /// task was closed
+    pub async fn failure(&mut self) -> CliError {
+        let handle = match self.handle.take() {
+            Some(handle) => handle,
+            None => return CliError::Internal("the archive was already closed".to_string()),
+        };
+        // a task that is still waiting for the next entry only returns once it sees the end
+        self.entries.take();
+
+        match handle.await {
+            Ok(Err(err)) => err,
+            Ok(Ok(())) => CliError::Internal("the archive was closed unexpectedly".to_string()),
+            Err(err) => CliError::Internal(format!("failed to write the archive: {}", err)),
+        }
+    }
+
+    /// Completes the archive after the last entry
+    pub async fn finish(mut self) -> Result<()> {
+        let handle = match self.handle.take() {
+            Some(handle) => handle,
+            None => return Ok(()),
+        };
+        self.entries.take();
+
+        handle
+            .await
+            .map_err(|err| CliError::Internal(format!("failed to write the archive: {}", err)))?
+    }
+
+    /// Stops the archive after a failed load and removes the incomplete archive file
+    pub async fn abort(mut self) {
+        self.entries.take();
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.await;
+        }
+
+        if let Err(err) = tokio::fs::remove_file(&self.path).await {
+            eprintln!("failed to remove the archive {:?}: {}", self.path, err);
+        }
+    }
+}
+
+fn write_tar<W: Write>(
+    mut builder: tar::Builder<W>,
+    mut entries: mpsc::Receiver<ArchiveEntry>,
+) -> Result<W> {
+    let mtime = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|x| x.as_secs())
+        .unwrap_or_default();
+
+    while let Some(entry) = entries.blocking_recv() {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(entry.len);
+        header.set_mode(ARCHIVE_FILE_MODE);
+        header.set_mtime(mtime);
+
+        let reader = ChunkReader::new(entry.chunks, entry.len);
+        builder.append_data(&mut header, &entry.path, reader)?;
+    }
+
+    return Ok(builder.into_inner()?);
+}
+
+fn write_zip(mut writer: ZipWriter<File>, mut entries: mpsc::Receiver<ArchiveEntry>) -> Result<()> {
+    while let Some(entry) = entries.blocking_recv() {
+        let options = FileOptions::default()
+            .compression_method(CompressionMethod::Deflated)
+            .unix_permissions(ARCHIVE_FILE_MODE)
+            .large_file(entry.len >= u32::MAX as u64);
+        writer
+            .start_file(entry.path.as_str(), options)
+            .map_err(zip_error)?;
+
+        let mut reader = ChunkReader::new(entry.chunks, entry.len);
+        std::io::copy(&mut reader, &mut writer)?;
+    }
+
+    writer.finish().map_err(zip_error)?.sync_all()?;
+
+    Ok(())
+}
+
+fn zip_error(err: zip::result::ZipError) -> CliError {
+    match err {
+        zip::result::ZipError::Io(err) => CliError::Io(err),
+        err => CliError::Internal(format!("failed to write the zip archive: {}", err)),
+    }
+}
+
+/// Blocking reader over the chunks of an archive entry. An entry that ends before all of its
+/// bytes were received or that receives more bytes than announced fails, the size of the entry
+/// is already written into its header
+struct ChunkReader {
+    chunks: mpsc::Receiver<Bytes>,
+    current: Bytes,
+    remaining: u64,
+}
+
+impl ChunkReader {
+    fn new(chunks: mpsc::Receiver<Bytes>, len: u64) -> Self {
+        return ChunkReader {
+            chunks: chunks,
+            current: Bytes::new(),
+            remaining: len,
+        };
+    }
+}
+
+impl Read for ChunkReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        while self.current.is_empty() {
+            match self.chunks.blocking_recv() {
+                Some(chunk) if chunk.len() as u64 > self.remaining => {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::InvalidData,
+                        "the download of an archive entry is larger than the object",
+                    ))
+                }
+                Some(chunk) => self.current = chunk,
+                None if self.remaining == 0 => return Ok(0),
+                None => {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::UnexpectedEof,
+                        "the download of an archive entry was aborted",
+                    ))
+                }
+            }
+        }
+
+        let len = std::cmp::min(buf.len(), self.current.len());
+        buf[..len].copy_from_slice(&self.current[..len]);
+        self.current.advance(len);
+        self.remaining -= len as u64;
+
+        Ok(len)
+    }
+}
@@ -0,0 +1,239 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use scienceobjectsdb_rust_api::sciobjectsdb::sciobjsdb::api::storage::services::v1::{
+    GetDatasetRequest, GetDatasetVersionRequest, GetProjectDatasetsRequest, GetProjectRequest,
+};
+use serde::Serialize;
+
+use crate::{
+    client::client,
+    output::views::{
+        self, DatasetIndexObjectGroupView, DatasetIndexObjectView, DatasetIndexView,
+        DatasetVersionView, DatasetView, ObjectView, ProjectIndexDatasetView, ProjectIndexView,
+        ProjectView,
+    },
+    util::{
+        cli::IndexFormat,
+        error::{CliError, Result},
+    },
+};
+
+use super::{download_handler::ObjectDownloadMessage, download_path_handler::DownloadPathHandler};
+
+/// Dataset id and dataset version id of an index file
+type DatasetIndexKey = (String, Option<String>);
+
+/// Collects the metadata of the loaded objects by dataset, the index files are created once all
+/// objects were loaded. Loads of a project additionally get a project index. Shared by all
+/// download workers
+pub struct DatasetIndex {
+    format: IndexFormat,
+    project_id: Option<String>,
+    datasets: Mutex<BTreeMap<DatasetIndexKey, BTreeMap<String, DatasetIndexObjectGroupView>>>,
+}
+
+impl DatasetIndex {
+    pub fn new(format: IndexFormat, project_id: Option<String>) -> Self {
+        return DatasetIndex {
+            format: format,
+            project_id: project_id,
+            datasets: Mutex::new(BTreeMap::new()),
+        };
+    }
+
+    /// Adds a loaded object, `path` is relative to the base path of the load. Single objects
+    /// are not part of a dataset index
+    pub fn record(&self, msg: &ObjectDownloadMessage, path: &Path, sha256: Option<String>) {
+        if self.format == IndexFormat::None || msg.target_path.is_some() {
+            return;
+        }
+
+        let mut datasets = match self.datasets.lock() {
+            Ok(datasets) => datasets,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+
+        let key = (
+            msg.object.dataset_id.clone(),
+            msg.dataset_version_id.clone(),
+        );
+        let object_group = datasets
+            .entry(key)
+            .or_default()
+            .entry(msg.object_group_id.clone())
+            .or_insert_with(|| DatasetIndexObjectGroupView {
+                id: msg.object_group_id.clone(),
+                name: msg.object_group_name.clone(),
+                revision_id: msg.object_group_revision_id.clone(),
+                revision: msg.object_group_revision,
+                labels: views::label_views(&msg.object_group_labels),
+                objects: Vec::new(),
+            });
+
+        object_group.objects.push(DatasetIndexObjectView {
+            path: path.display().to_string(),
+            sha256: sha256,
+            object: ObjectView::from(&msg.object),
+        });
+    }
+
+    /// The content of the index files of all recorded datasets by the path of the file
+    pub async fn files<T: DownloadPathHandler>(
+        &self,
+        client: &client::Client,
+        path_handler: &T,
+        basepath: &Path,
+    ) -> Result<Vec<(PathBuf, Vec<u8>)>> {
+        if self.format == IndexFormat::None {
+            return Ok(Vec::new());
+        }
+
+        let datasets = match self.datasets.lock() {
+            Ok(mut datasets) => std::mem::take(&mut *datasets),
+            Err(poisoned) => std::mem::take(&mut *poisoned.into_inner()),
+        };
+
+        let mut files = Vec::new();
+        let mut dataset_index_files: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        for ((dataset_id, dataset_version_id), object_groups) in datasets {
+            let dataset = client
+                .call(
+                    &client.dataset_service,
+                    GetDatasetRequest {
+                        id: dataset_id.clone(),
+                    },
+                    |mut service, req| async move { service.get_dataset(req).await },
+                )
+                .await?
+                .dataset
+                .ok_or_else(|| {
+                    CliError::InvalidResponse("dataset response was empty".to_string())
+                })?;
+
+            let dataset_version = match &dataset_version_id {
+                Some(dataset_version_id) => client
+                    .call(
+                        &client.dataset_service,
+                        GetDatasetVersionRequest {
+                            id: dataset_version_id.clone(),
+                        },
+                        |mut service, req| async move { service.get_dataset_version(req).await },
+                    )
+                    .await?
+                    .dataset_version
+                    .map(|x| DatasetVersionView::from(&x)),
+                None => None,
+            };
+
+            let mut object_groups: Vec<DatasetIndexObjectGroupView> =
+                object_groups.into_values().collect();
+            for object_group in object_groups.iter_mut() {
+                object_group.objects.sort_by(|a, b| a.path.cmp(&b.path));
+            }
+
+            let index = DatasetIndexView {
+                dataset: DatasetView::from(&dataset),
+                dataset_version: dataset_version,
+                object_groups: object_groups,
+            };
+            let file_name = match &dataset_version_id {
+                Some(dataset_version_id) => format!("datasetversion_{}", dataset_version_id),
+                None => format!("dataset_{}", dataset_id),
+            };
+            let (extension, content) = self.serialize(&index)?;
+
+            let index_path = path_handler
+                .dataset_index_path(Path::new(""), &dataset)
+                .join(format!("{}.{}", file_name, extension));
+            dataset_index_files
+                .entry(dataset_id)
+                .or_default()
+                .push(index_path.display().to_string());
+            files.push((basepath.join(index_path), content));
+        }
+
+        if let Some(project_id) = &self.project_id {
+            let (extension, content) = self
+                .project_index(client, project_id, dataset_index_files)
+                .await?;
+            let index_path = path_handler
+                .project_index_path(basepath, project_id)
+                .join(format!("project_{}.{}", project_id, extension));
+            files.push((index_path, content));
+        }
+
+        return Ok(files);
+    }
+
+    /// The project with all of its datasets, also those that no object was loaded from
+    async fn project_index(
+        &self,
+        client: &client::Client,
+        project_id: &str,
+        mut dataset_index_files: BTreeMap<String, Vec<String>>,
+    ) -> Result<(&'static str, Vec<u8>)> {
+        let project = client
+            .call(
+                &client.project_service,
+                GetProjectRequest {
+                    id: project_id.to_string(),
+                },
+                |mut service, req| async move { service.get_project(req).await },
+            )
+            .await?
+            .project
+            .ok_or_else(|| CliError::InvalidResponse("project response was empty".to_string()))?;
+        let project_datasets = client
+            .call(
+                &client.project_service,
+                GetProjectDatasetsRequest {
+                    id: project_id.to_string(),
+                },
+                |mut service, req| async move { service.get_project_datasets(req).await },
+            )
+            .await?
+            .datasets;
+
+        let index = ProjectIndexView {
+            project: ProjectView::from(&project),
+            datasets: project_datasets
+                .iter()
+                .map(|dataset| ProjectIndexDatasetView {
+                    dataset: DatasetView::from(dataset),
+                    index_files: dataset_index_files.remove(&dataset.id).unwrap_or_default(),
+                })
+                .collect(),
+        };
+
+        return self.serialize(&index);
+    }
+
+    /// The file extension and content of an index file
+    fn serialize<T: Serialize>(&self, index: &T) -> Result<(&'static str, Vec<u8>)> {
+        match self.format {
+            IndexFormat::Yaml => Ok(("yaml", serde_yaml::to_string(index)?.into_bytes())),
+            _ => Ok(("json", serde_json::to_vec_pretty(index)?)),
+        }
+    }
+
+    /// Writes the index files below the base path
+    pub async fn write<T: DownloadPathHandler>(
+        &self,
+        client: &client::Client,
+        path_handler: &T,
+        basepath: &Path,
+    ) -> Result<()> {
+        for (index_path, content) in self.files(client, path_handler, basepath).await? {
+            if let Some(index_dir) = index_path.parent() {
+                tokio::fs::create_dir_all(index_dir).await?;
+            }
+            tokio::fs::write(index_path, content).await?;
+        }
+
+        Ok(())
+    }
+}
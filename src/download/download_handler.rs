@@ -1,159 +1,434 @@
-use std::{error, path::Path};
-
-use crate::{client::client, util::cli};
+use std::path::{Path, PathBuf};
+
+use crate::{
+    client::{client, paging, rate_limit::RateLimiter},
+    util::{
+        checksum::{self, ChecksumMismatch},
+        cli,
+        error::{CliError, Result},
+        progress::{FileProgress, TransferProgress},
+    },
+};
 
-use super::download_path_handler::DownloadPathHandler;
+use super::{
+    archive::ArchiveWriter, dataset_index::DatasetIndex,
+    download_path_handler::DownloadPathHandler, path_claims::PathClaims,
+};
 use async_channel::bounded;
+use bytes::Bytes;
 use futures::{
     future::{try_join, try_join_all},
-    StreamExt,
+    StreamExt, TryStreamExt,
 };
 use scienceobjectsdb_rust_api::sciobjectsdb::sciobjsdb::api::storage::{
-    models::v1::{Object, PageRequest},
+    models::v1::{Label, Object},
     services::v1::{
-        CreateDownloadLinkRequest, GetDatasetObjectGroupsRequest, GetObjectGroupRequest,
-        GetProjectDatasetsRequest,
+        CreateDownloadLinkRequest, GetDatasetRequest, GetDatasetVersionRequest,
+        GetObjectGroupRequest, GetProjectDatasetsRequest,
     },
 };
 
-use tokio::io::AsyncWriteExt;
-
-const DATASET_OBJECT_GROUP_PAGE_SIZE: u64 = 500;
-const OBJECT_GROUP_DOWNLOAD_REQUEST_QUEUE_SIZE: usize = 500;
+use reqwest::{header::RANGE, StatusCode};
+use sha2::{Digest, Sha256};
+use tokio::{
+    io::AsyncWriteExt,
+    sync::{mpsc, Mutex},
+};
 
-type Result<T> = std::result::Result<T, Box<dyn error::Error>>;
+const PARTIAL_DOWNLOAD_FILE_SUFFIX: &str = ".part";
 
 pub struct DownloadHandler {}
 
+/// Settings and state that are shared by the listing and the workers of one load
+struct LoadContext<'a, T: DownloadPathHandler> {
+    client: client::Client,
+    path_handler: &'a T,
+    path_claims: &'a PathClaims,
+    dataset_index: &'a DatasetIndex,
+    progress: TransferProgress,
+    basepath: &'a Path,
+    labels: &'a [cli::LabelSelector],
+    workers: usize,
+    resume: bool,
+}
+
 #[derive(Clone)]
 pub struct ObjectDownloadMessage {
     pub object: Object,
+    pub object_group_id: String,
     pub object_group_name: String,
+    pub object_group_labels: Vec<Label>,
+    pub object_group_revision_id: String,
+    pub object_group_revision: i64,
+    pub dataset_name: String,
+    pub dataset_version_id: Option<String>,
+    /// Explicit target of single objects, the path style is not applied
+    pub target_path: Option<PathBuf>,
+}
+
+impl ObjectDownloadMessage {
+    /// The path the object is stored at, according to the path style
+    pub fn file_path<T: DownloadPathHandler>(&self, path_handler: &T, basepath: &Path) -> PathBuf {
+        match &self.target_path {
+            Some(target_path) => target_path.clone(),
+            None => path_handler.create_file_path(basepath, self),
+        }
+    }
 }
 
 impl DownloadHandler {
-    pub async fn download<T: DownloadPathHandler>(request: cli::Load, client: client::Client) {
-        let (sender, recv) = bounded(OBJECT_GROUP_DOWNLOAD_REQUEST_QUEUE_SIZE);
-        let basepath = request.path.clone();
+    pub async fn download<T: DownloadPathHandler>(
+        request: cli::Load,
+        client: client::Client,
+        path_handler: T,
+    ) -> Result<()> {
+        if let Some(archive) = request.archive.clone() {
+            return DownloadHandler::download_archive(
+                request,
+                Path::new(&archive),
+                client,
+                &path_handler,
+            )
+            .await;
+        }
+
+        let (sender, recv) = bounded(request.queue_size());
+        let basepath = request.path.clone().unwrap_or_default();
         let path = Path::new(basepath.as_str());
+        let progress = TransferProgress::new("Downloaded");
+        let path_claims = PathClaims::new(request.on_collision);
+        let dataset_index = DatasetIndex::new(request.index_format, request.project_id());
+        let ctx = LoadContext {
+            client: client.clone(),
+            path_handler: &path_handler,
+            path_claims: &path_claims,
+            dataset_index: &dataset_index,
+            progress: progress.clone(),
+            basepath: path,
+            labels: &request.labels,
+            workers: request.workers(),
+            resume: request.resume,
+        };
 
-        let worker_future = match request.path_style {
-            cli::DownloadPathStyle::Canonical => {
-                DownloadHandler::start_download_worker::<T>(10, path, client.clone(), recv)
-            }
-            cli::DownloadPathStyle::Flat => {
-                DownloadHandler::start_download_worker::<T>(10, path, client.clone(), recv)
+        let worker_future = DownloadHandler::start_download_worker(&ctx, recv);
+        let resource_future = DownloadHandler::handle_claimed_resources(
+            &ctx,
+            request.resource,
+            cli::required_id(&request.id)?,
+            sender,
+        );
+
+        let result = try_join(worker_future, resource_future).await;
+        progress.finish();
+
+        // the index is also written if the load failed, as a record of the loaded files
+        let index_result = dataset_index.write(&client, &path_handler, path).await;
+        let (checksum_mismatches, skipped_object_groups) = result?;
+        index_result?;
+        if !checksum_mismatches.is_empty() {
+            return Err(CliError::ChecksumMismatch(checksum_mismatches));
+        }
+        path_claims.finish()?;
+
+        if skipped_object_groups > 0 {
+            return Err(CliError::SkippedObjectGroups(skipped_object_groups));
+        }
+
+        Ok(())
+    }
+
+    /// Streams the objects into an archive, the entries are named like the files a download
+    /// with the path style would create
+    async fn download_archive<T: DownloadPathHandler>(
+        request: cli::Load,
+        archive_path: &Path,
+        client: client::Client,
+        path_handler: &T,
+    ) -> Result<()> {
+        let mut archive = ArchiveWriter::create(archive_path)?;
+        let (sender, recv) = bounded(request.queue_size());
+        let progress = TransferProgress::new("Archived");
+        let path_claims = PathClaims::new(request.on_collision);
+        let dataset_index = DatasetIndex::new(request.index_format, request.project_id());
+        // entries are named relative to the root of the archive and can not be resumed
+        let ctx = LoadContext {
+            client: client.clone(),
+            path_handler: path_handler,
+            path_claims: &path_claims,
+            dataset_index: &dataset_index,
+            progress: progress.clone(),
+            basepath: Path::new(""),
+            labels: &request.labels,
+            workers: request.workers(),
+            resume: false,
+        };
+
+        let archive_future = DownloadHandler::archive_object_loop(&mut archive, &ctx, recv);
+        let resource_future = DownloadHandler::handle_claimed_resources(
+            &ctx,
+            request.resource,
+            cli::required_id(&request.id)?,
+            sender,
+        );
+
+        let result = try_join(archive_future, resource_future).await;
+        progress.finish();
+        let skipped_object_groups = match result {
+            Ok((_, skipped_object_groups)) => skipped_object_groups,
+            Err(err) => {
+                archive.abort().await;
+                return Err(err);
             }
         };
 
-        let resource_future = DownloadHandler::handle_resources(request, client.clone(), sender);
+        for (index_path, content) in dataset_index
+            .files(&client, path_handler, Path::new(""))
+            .await?
+        {
+            let chunks = archive
+                .add_entry(
+                    index_path.to_string_lossy().to_string(),
+                    content.len() as u64,
+                )
+                .await?;
+            if chunks.send(Bytes::from(content)).await.is_err() {
+                return Err(archive.failure().await);
+            }
+        }
+        archive.finish().await?;
+        path_claims.finish()?;
+
+        if skipped_object_groups > 0 {
+            return Err(CliError::SkippedObjectGroups(skipped_object_groups));
+        }
 
-        try_join(worker_future, resource_future).await.unwrap();
+        Ok(())
     }
 
-    async fn handle_resources(
-        request: cli::Load,
+    /// Like handle_resources, but if the collision policy needs reservations all objects are
+    /// listed and their paths reserved before the first message is sent. Returns the number of
+    /// skipped object groups
+    async fn handle_claimed_resources<T: DownloadPathHandler>(
+        ctx: &LoadContext<'_, T>,
+        resource: cli::Resource,
+        id: String,
+        sender: async_channel::Sender<ObjectDownloadMessage>,
+    ) -> Result<u64> {
+        if !ctx.path_claims.needs_reservations() {
+            return DownloadHandler::handle_resources(
+                resource,
+                id,
+                ctx.basepath,
+                ctx.labels,
+                ctx.workers,
+                ctx.client.clone(),
+                sender,
+            )
+            .await;
+        }
+
+        let (listed_sender, listed_recv) = async_channel::unbounded();
+        let skipped_object_groups = DownloadHandler::handle_resources(
+            resource,
+            id,
+            ctx.basepath,
+            ctx.labels,
+            ctx.workers,
+            ctx.client.clone(),
+            listed_sender,
+        )
+        .await?;
+
+        let mut object_msgs = Vec::new();
+        while let Ok(object_msg) = listed_recv.try_recv() {
+            ctx.path_claims.reserve(
+                object_msg.file_path(ctx.path_handler, ctx.basepath),
+                &object_msg.object,
+            );
+            object_msgs.push(object_msg);
+        }
+        for object_msg in object_msgs {
+            sender.send(object_msg).await?;
+        }
+
+        Ok(skipped_object_groups)
+    }
+
+    /// Sends a message for every object of the given resource that matches the labels. The
+    /// datasets of a project are listed by up to `concurrency` tasks at the same time. Object
+    /// groups without a current revision and a requested object group that does not have the
+    /// labels are reported and skipped, their number is returned
+    pub async fn handle_resources(
+        resource: cli::Resource,
+        id: String,
+        basepath: &Path,
+        labels: &[cli::LabelSelector],
+        concurrency: usize,
         client: client::Client,
         sender: async_channel::Sender<ObjectDownloadMessage>,
-    ) -> Result<()> {
-        match request.resource {
+    ) -> Result<u64> {
+        let skipped_object_groups = match resource {
             cli::Resource::Project => {
-                DownloadHandler::download_project(client, request.id, sender).await?
+                DownloadHandler::download_project(client, id, labels, concurrency, sender).await?
             }
             cli::Resource::Dataset => {
-                DownloadHandler::download_dataset(client, request.id, sender).await?
+                let dataset_name = DownloadHandler::dataset_name(&client, id.clone()).await?;
+                DownloadHandler::download_dataset(client, id, dataset_name, labels, sender).await?
+            }
+            // object group revisions of dataset versions and single object groups can not be
+            // filtered by the server, so all labels are checked here
+            cli::Resource::DatasetVersion => {
+                DownloadHandler::download_dataset_version(client, id, labels, sender).await?;
+                0
             }
-            cli::Resource::DatasetVersion => todo!(),
             cli::Resource::ObjectGroup => {
-                DownloadHandler::download_object_group(client, request.id, sender).await?
+                DownloadHandler::download_object_group(client, id, labels, sender).await?
+            }
+            cli::Resource::Object => {
+                DownloadHandler::download_object(client, id, basepath, sender).await?;
+                0
             }
         };
 
-        Ok(())
+        Ok(skipped_object_groups)
     }
 
     async fn start_download_worker<T: DownloadPathHandler>(
-        workercount: usize,
-        basepath: &Path,
-        client: client::Client,
+        ctx: &LoadContext<'_, T>,
         recv: async_channel::Receiver<ObjectDownloadMessage>,
-    ) -> Result<()> {
+    ) -> Result<Vec<ChecksumMismatch>> {
         let mut worker = Vec::new();
 
-        for _ in 0..workercount {
-            worker.push(Self::download_object_loop::<T>(
-                basepath,
-                client.clone(),
-                recv.clone(),
-            ));
+        for _ in 0..ctx.workers {
+            worker.push(Self::download_object_loop(ctx, recv.clone()));
         }
 
-        try_join_all(worker).await?;
+        let checksum_mismatches = try_join_all(worker).await?;
 
-        Ok(())
+        Ok(checksum_mismatches.into_iter().flatten().collect())
     }
 
     async fn download_project(
-        mut client: client::Client,
+        client: client::Client,
         project_id: String,
+        labels: &[cli::LabelSelector],
+        concurrency: usize,
         sender: async_channel::Sender<ObjectDownloadMessage>,
-    ) -> Result<()> {
+    ) -> Result<u64> {
         let project_datasets = client
-            .project_service
-            .get_project_datasets(GetProjectDatasetsRequest { id: project_id })
-            .await?
-            .into_inner();
-
-        for dataset in project_datasets.datasets {
-            DownloadHandler::download_dataset(client.clone(), dataset.id, sender.clone()).await?;
-        }
+            .call(
+                &client.project_service,
+                GetProjectDatasetsRequest { id: project_id },
+                |mut service, req| async move { service.get_project_datasets(req).await },
+            )
+            .await?;
+
+        let skipped_object_groups = futures::stream::iter(project_datasets.datasets)
+            .map(|dataset| {
+                DownloadHandler::download_dataset(
+                    client.clone(),
+                    dataset.id,
+                    dataset.name,
+                    labels,
+                    sender.clone(),
+                )
+            })
+            .buffer_unordered(std::cmp::max(concurrency, 1))
+            .try_collect::<Vec<u64>>()
+            .await?;
 
-        Ok(())
+        Ok(skipped_object_groups.into_iter().sum())
     }
 
     async fn download_dataset(
-        mut client: client::Client,
+        client: client::Client,
         dataset_id: String,
+        dataset_name: String,
+        labels: &[cli::LabelSelector],
         sender: async_channel::Sender<ObjectDownloadMessage>,
-    ) -> Result<()> {
-        let mut prev_last_uuid = "".to_string();
-
-        loop {
-            let object_groups = client
-                .dataset_service
-                .get_dataset_object_groups(GetDatasetObjectGroupsRequest {
-                    id: dataset_id.clone(),
-                    page_request: Some(PageRequest {
-                        page_size: DATASET_OBJECT_GROUP_PAGE_SIZE,
-                        last_uuid: prev_last_uuid.clone(),
-                    }),
-                    label_filter: None,
-                })
-                .await?
-                .into_inner();
-
-            let object_group_count = object_groups.object_groups.len() as u64;
-            for object_group in object_groups.object_groups {
-                match object_group.current_revision {
-                    // if failed to retrieve current_revision
-                    // the next object group will be tried
-                    Some(object_group_revision) => {
-                        for object in object_group_revision.objects {
-                            prev_last_uuid = object.id.clone();
-                            let msg = ObjectDownloadMessage {
-                                object: object,
-                                object_group_name: object_group_revision.name.clone(),
-                            };
-
-                            sender.send(msg).await?;
-                        }
-                    }
-                    None => continue,
+    ) -> Result<u64> {
+        let object_groups =
+            paging::dataset_object_groups(client, dataset_id.clone(), labels.to_vec());
+        futures::pin_mut!(object_groups);
+
+        let mut skipped_object_groups = 0;
+        while let Some(object_group) = object_groups.try_next().await? {
+            let object_group_revision = match object_group.current_revision {
+                Some(object_group_revision) => object_group_revision,
+                None => {
+                    eprintln!(
+                        "skipped object group {} of dataset {}: it has no current revision",
+                        object_group.id, dataset_id
+                    );
+                    skipped_object_groups += 1;
+                    continue;
+                }
+            };
+
+            for object in object_group_revision.objects {
+                let msg = ObjectDownloadMessage {
+                    object: object,
+                    object_group_id: object_group_revision.object_group_id.clone(),
+                    object_group_name: object_group_revision.name.clone(),
+                    object_group_labels: object_group_revision.labels.clone(),
+                    object_group_revision_id: object_group_revision.id.clone(),
+                    object_group_revision: object_group_revision.revision_number,
+                    dataset_name: dataset_name.clone(),
+                    dataset_version_id: None,
+                    target_path: None,
                 };
+
+                sender.send(msg).await?;
+            }
+        }
+
+        Ok(skipped_object_groups)
+    }
+
+    async fn download_dataset_version(
+        client: client::Client,
+        dataset_version_id: String,
+        labels: &[cli::LabelSelector],
+        sender: async_channel::Sender<ObjectDownloadMessage>,
+    ) -> Result<()> {
+        let dataset_version = client
+            .call(
+                &client.dataset_service,
+                GetDatasetVersionRequest {
+                    id: dataset_version_id.clone(),
+                },
+                |mut service, req| async move { service.get_dataset_version(req).await },
+            )
+            .await?
+            .dataset_version
+            .ok_or_else(|| {
+                CliError::InvalidResponse("dataset version response was empty".to_string())
+            })?;
+        let dataset_name =
+            DownloadHandler::dataset_name(&client, dataset_version.dataset_id).await?;
+        let object_group_revisions =
+            paging::dataset_version_object_groups(client, dataset_version_id.clone());
+        futures::pin_mut!(object_group_revisions);
+
+        while let Some(object_group_revision) = object_group_revisions.try_next().await? {
+            if !cli::matches_labels(labels, &object_group_revision.labels) {
+                continue;
             }
 
-            if object_group_count != DATASET_OBJECT_GROUP_PAGE_SIZE {
-                break;
+            for object in object_group_revision.objects {
+                let msg = ObjectDownloadMessage {
+                    object: object,
+                    object_group_id: object_group_revision.object_group_id.clone(),
+                    object_group_name: object_group_revision.name.clone(),
+                    object_group_labels: object_group_revision.labels.clone(),
+                    object_group_revision_id: object_group_revision.id.clone(),
+                    object_group_revision: object_group_revision.revision_number,
+                    dataset_name: dataset_name.clone(),
+                    dataset_version_id: Some(dataset_version_id.clone()),
+                    target_path: None,
+                };
+
+                sender.send(msg).await?;
             }
         }
 
@@ -161,67 +436,491 @@ impl DownloadHandler {
     }
 
     async fn download_object_group(
-        mut client: client::Client,
+        client: client::Client,
         object_group_id: String,
+        labels: &[cli::LabelSelector],
         sender: async_channel::Sender<ObjectDownloadMessage>,
-    ) -> Result<()> {
+    ) -> Result<u64> {
         let object_group_response = client
-            .dataset_object_service
-            .get_object_group(GetObjectGroupRequest {
-                id: object_group_id,
-                pagination: None,
-            })
+            .call(
+                &client.dataset_object_service,
+                GetObjectGroupRequest {
+                    id: object_group_id,
+                    pagination: None,
+                },
+                |mut service, req| async move { service.get_object_group(req).await },
+            )
+            .await?;
+        let object_group = object_group_response.object_group.ok_or_else(|| {
+            CliError::InvalidResponse("object group response was empty".to_string())
+        })?;
+
+        // the requested object group is reported as skipped instead of loading nothing
+        let object_group_revision = match object_group.current_revision {
+            Some(object_group_revision) => object_group_revision,
+            None => {
+                eprintln!(
+                    "skipped object group {}: it has no current revision",
+                    object_group.id
+                );
+                return Ok(1);
+            }
+        };
+        if !cli::matches_labels(labels, &object_group_revision.labels) {
+            eprintln!(
+                "skipped object group {}: its current revision does not have the given labels",
+                object_group.id
+            );
+            return Ok(1);
+        }
+
+        let dataset_name =
+            DownloadHandler::dataset_name(&client, object_group.dataset_id.clone()).await?;
+        for object in object_group_revision.objects {
+            let msg = ObjectDownloadMessage {
+                object: object,
+                object_group_id: object_group_revision.object_group_id.clone(),
+                object_group_name: object_group_revision.name.clone(),
+                object_group_labels: object_group_revision.labels.clone(),
+                object_group_revision_id: object_group_revision.id.clone(),
+                object_group_revision: object_group_revision.revision_number,
+                dataset_name: dataset_name.clone(),
+                dataset_version_id: None,
+                target_path: None,
+            };
+            sender.send(msg).await?;
+        }
+
+        return Ok(0);
+    }
+
+    /// A single object is stored in the base path if it is a directory, otherwise the base path
+    /// is used as file name
+    async fn download_object(
+        client: client::Client,
+        object_id: String,
+        basepath: &Path,
+        sender: async_channel::Sender<ObjectDownloadMessage>,
+    ) -> Result<()> {
+        let object = client
+            .call(
+                &client.object_load_service,
+                CreateDownloadLinkRequest {
+                    id: object_id,
+                    ..Default::default()
+                },
+                |mut service, req| async move { service.create_download_link(req).await },
+            )
             .await?
-            .into_inner();
-        let object_group = object_group_response.object_group.unwrap();
-        // not sure if this makes sense
-        match object_group.current_revision {
-            Some(object_group_revision) => {
-                for object in object_group_revision.objects {
-                    let msg = ObjectDownloadMessage {
-                        object: object,
-                        object_group_name: object_group_revision.name.clone(),
-                    };
-                    sender.send(msg).await?;
-                }
+            .object
+            .ok_or_else(|| CliError::InvalidResponse("object response was empty".to_string()))?;
+        let dataset_name =
+            DownloadHandler::dataset_name(&client, object.dataset_id.clone()).await?;
+
+        // archives use an empty base path
+        let is_dir = match tokio::fs::metadata(basepath).await {
+            Ok(metadata) => metadata.is_dir(),
+            Err(_) => {
+                basepath.as_os_str().is_empty()
+                    || basepath
+                        .to_string_lossy()
+                        .ends_with(std::path::MAIN_SEPARATOR)
             }
-            None => (),
         };
+        let target_path = match is_dir {
+            true => basepath.join(format!("{}.{}", object.filename, object.filetype)),
+            false => basepath.to_path_buf(),
+        };
+
+        let msg = ObjectDownloadMessage {
+            object: object,
+            object_group_id: "".to_string(),
+            object_group_name: "".to_string(),
+            object_group_labels: Vec::new(),
+            object_group_revision_id: "".to_string(),
+            object_group_revision: 0,
+            dataset_name: dataset_name,
+            dataset_version_id: None,
+            target_path: Some(target_path),
+        };
+        sender.send(msg).await?;
 
         return Ok(());
     }
 
+    /// The name of the dataset is not part of the objects, but can be used in path templates
+    async fn dataset_name(client: &client::Client, dataset_id: String) -> Result<String> {
+        let dataset = client
+            .call(
+                &client.dataset_service,
+                GetDatasetRequest { id: dataset_id },
+                |mut service, req| async move { service.get_dataset(req).await },
+            )
+            .await?
+            .dataset
+            .ok_or_else(|| CliError::InvalidResponse("dataset response was empty".to_string()))?;
+
+        return Ok(dataset.name);
+    }
+
     async fn download_object_loop<T: DownloadPathHandler>(
-        basepath: &Path,
-        mut client: client::Client,
+        ctx: &LoadContext<'_, T>,
+        mut recv: async_channel::Receiver<ObjectDownloadMessage>,
+    ) -> Result<Vec<ChecksumMismatch>> {
+        let mut checksum_mismatches = Vec::new();
+
+        while let Some(object_msg) = recv.next().await {
+            let object = &object_msg.object;
+            let full_file_path = match ctx
+                .path_claims
+                .claim(object_msg.file_path(ctx.path_handler, ctx.basepath), object)
+            {
+                Some(full_file_path) => full_file_path,
+                None => continue,
+            };
+
+            let relative_file_path = full_file_path
+                .strip_prefix(ctx.basepath)
+                .unwrap_or(&full_file_path)
+                .to_path_buf();
+
+            // files that are already complete from a previous run are skipped
+            if ctx.resume && DownloadHandler::is_complete(&full_file_path, object).await? {
+                ctx.progress.skip_file(object.content_len as u64);
+                ctx.dataset_index.record(
+                    &object_msg,
+                    &relative_file_path,
+                    checksum::sha256_from_labels(&object.labels),
+                );
+                continue;
+            }
+
+            let object_link = ctx
+                .client
+                .call(
+                    &ctx.client.object_load_service,
+                    CreateDownloadLinkRequest {
+                        id: object.id.clone(),
+                        ..Default::default()
+                    },
+                    |mut service, req| async move { service.create_download_link(req).await },
+                )
+                .await?;
+
+            if let Some(object_group_path) = full_file_path.parent() {
+                tokio::fs::create_dir_all(object_group_path).await?;
+            }
+            let partial_file_path = DownloadHandler::partial_file_path(&full_file_path);
+            let file_progress = ctx.progress.start_file(
+                full_file_path
+                    .file_name()
+                    .unwrap_or_default()
+                    .to_string_lossy()
+                    .to_string(),
+                object.content_len as u64,
+            );
+            // retried downloads continue with the data that was already written
+            let result = ctx
+                .client
+                .retry_policy
+                .retry(|attempt| {
+                    DownloadHandler::download_file(
+                        object_link.download_link.clone(),
+                        &partial_file_path,
+                        object.content_len as u64,
+                        ctx.resume || attempt > 0,
+                        &file_progress,
+                        &ctx.client.rate_limiter,
+                    )
+                })
+                .await;
+            let sha256 = match result {
+                Ok(sha256) => sha256,
+                Err(err) => {
+                    file_progress.fail();
+                    return Err(err);
+                }
+            };
+
+            // corrupted files are removed so that they are downloaded again by the next run
+            if let Some(expected) = checksum::sha256_from_labels(&object.labels) {
+                if expected != sha256 {
+                    file_progress.fail();
+                    tokio::fs::remove_file(&partial_file_path).await?;
+                    checksum_mismatches.push(ChecksumMismatch {
+                        path: full_file_path.display().to_string(),
+                        expected: expected,
+                        actual: sha256,
+                    });
+                    continue;
+                }
+            }
+
+            // the complete file only appears under its final name once it was fully written
+            tokio::fs::rename(&partial_file_path, &full_file_path).await?;
+            file_progress.finish();
+            ctx.dataset_index
+                .record(&object_msg, &relative_file_path, Some(sha256));
+        }
+
+        return Ok(checksum_mismatches);
+    }
+
+    async fn archive_object_loop<T: DownloadPathHandler>(
+        archive: &mut ArchiveWriter,
+        ctx: &LoadContext<'_, T>,
         mut recv: async_channel::Receiver<ObjectDownloadMessage>,
     ) -> Result<()> {
         while let Some(object_msg) = recv.next().await {
-            let object_link = client
-                .object_load_service
-                .create_download_link(CreateDownloadLinkRequest {
-                    id: object_msg.object.id,
-                    ..Default::default()
+            let object = &object_msg.object;
+            let entry_path = match ctx
+                .path_claims
+                .claim(object_msg.file_path(ctx.path_handler, ctx.basepath), object)
+            {
+                Some(entry_path) => entry_path.to_string_lossy().to_string(),
+                None => continue,
+            };
+
+            let object_link = ctx
+                .client
+                .call(
+                    &ctx.client.object_load_service,
+                    CreateDownloadLinkRequest {
+                        id: object.id.clone(),
+                        ..Default::default()
+                    },
+                    |mut service, req| async move { service.create_download_link(req).await },
+                )
+                .await?;
+
+            let content_len = object.content_len as u64;
+            let chunks = archive.add_entry(entry_path.clone(), content_len).await?;
+            let file_progress = ctx.progress.start_file(entry_path.clone(), content_len);
+            let entry_state = Mutex::new((0, Sha256::new()));
+
+            // retried downloads continue after the data that was already written to the archive
+            let result = ctx
+                .client
+                .retry_policy
+                .retry(|_| {
+                    DownloadHandler::stream_archive_entry(
+                        object_link.download_link.clone(),
+                        &entry_state,
+                        &chunks,
+                        &file_progress,
+                        &ctx.client.rate_limiter,
+                    )
                 })
-                .await?
-                .into_inner();
+                .await;
+            if let Err(err) = result {
+                file_progress.fail();
+                return match chunks.is_closed() {
+                    true => Err(archive.failure().await),
+                    false => Err(err),
+                };
+            }
+            drop(chunks);
+
+            // the entry is already part of the archive, so a corrupted object fails the archive
+            let (_, hasher) = entry_state.into_inner();
+            let sha256 = hex::encode(hasher.finalize());
+            if let Some(expected) = checksum::sha256_from_labels(&object.labels) {
+                if expected != sha256 {
+                    file_progress.fail();
+                    return Err(CliError::ChecksumMismatch(vec![ChecksumMismatch {
+                        path: entry_path,
+                        expected: expected,
+                        actual: sha256,
+                    }]));
+                }
+            }
+            file_progress.finish();
+            ctx.dataset_index
+                .record(&object_msg, Path::new(&entry_path), Some(sha256));
+        }
+
+        Ok(())
+    }
+
+    async fn stream_archive_entry(
+        download_link: String,
+        entry_state: &Mutex<(u64, Sha256)>,
+        chunks: &mpsc::Sender<Bytes>,
+        progress: &FileProgress,
+        rate_limiter: &RateLimiter,
+    ) -> Result<()> {
+        let mut entry_state = entry_state.lock().await;
+        let (written, hasher) = &mut *entry_state;
 
-            let object = &object_link.object.unwrap();
-            let object_group_path =
-                T::create_object_group_path(basepath, object, object_msg.object_group_name);
+        let http_client = reqwest::Client::new();
+        let mut get_request = http_client.get(download_link);
+        if *written > 0 {
+            get_request = get_request.header(RANGE, format!("bytes={}-", written));
+        }
+        let mut get_response = get_request.send().await?.error_for_status()?;
 
-            tokio::fs::create_dir_all(&object_group_path).await?;
-            let full_file_path = T::create_file_path(&object_group_path, object);
-            let mut file = tokio::fs::File::create(full_file_path).await?;
+        // if the range request was not honored the data that was already written is skipped
+        let mut skip = match get_response.status() {
+            StatusCode::PARTIAL_CONTENT => 0,
+            _ => *written,
+        };
 
-            let mut get_response = reqwest::get(object_link.download_link).await?;
-            while let Some(chunk) = get_response.chunk().await? {
-                file.write(&chunk).await?;
+        while let Some(mut chunk) = get_response.chunk().await? {
+            if skip > 0 {
+                let skipped = std::cmp::min(skip, chunk.len() as u64);
+                chunk = chunk.slice(skipped as usize..);
+                skip -= skipped;
+            }
+            if chunk.is_empty() {
+                continue;
             }
 
-            file.flush().await?;
+            rate_limiter.acquire(chunk.len()).await;
+            progress.inc(chunk.len() as u64);
+            hasher.update(&chunk);
+            *written += chunk.len() as u64;
+            chunks.send(chunk).await.map_err(|_| {
+                CliError::Internal("the archive was closed unexpectedly".to_string())
+            })?;
         }
 
-        return Ok(());
+        Ok(())
+    }
+
+    async fn download_file(
+        download_link: String,
+        partial_file_path: &Path,
+        content_len: u64,
+        resume: bool,
+        progress: &FileProgress,
+        rate_limiter: &RateLimiter,
+    ) -> Result<String> {
+        let mut offset = 0;
+        if resume {
+            if let Ok(metadata) = tokio::fs::metadata(partial_file_path).await {
+                offset = metadata.len();
+            }
+        }
+
+        if offset > 0 && offset == content_len {
+            progress.set_position(offset);
+            return checksum::sha256_file(partial_file_path).await;
+        }
+        // a partial file larger than the object does not belong to it and is downloaded again
+        if offset > content_len {
+            offset = 0;
+        }
+
+        let http_client = reqwest::Client::new();
+        let mut get_request = http_client.get(download_link);
+        if offset > 0 {
+            get_request = get_request.header(RANGE, format!("bytes={}-", offset));
+        }
+        let mut get_response = get_request.send().await?.error_for_status()?;
+
+        // if the range request was not honored the whole file is transferred again
+        let mut hasher = Sha256::new();
+        let (mut file, mut received) = match get_response.status() {
+            StatusCode::PARTIAL_CONTENT => {
+                progress.set_position(offset);
+                checksum::update_from_file(&mut hasher, partial_file_path).await?;
+                let file = tokio::fs::OpenOptions::new()
+                    .append(true)
+                    .open(partial_file_path)
+                    .await?;
+                (file, offset)
+            }
+            _ => {
+                progress.set_position(0);
+                (tokio::fs::File::create(partial_file_path).await?, 0)
+            }
+        };
+
+        while let Some(chunk) = get_response.chunk().await? {
+            rate_limiter.acquire(chunk.len()).await;
+            progress.inc(chunk.len() as u64);
+            hasher.update(&chunk);
+            file.write_all(&chunk).await?;
+            received += chunk.len() as u64;
+        }
+
+        file.flush().await?;
+
+        // a truncated response is continued by the retry, a partial file that is too large is
+        // downloaded again
+        if received != content_len {
+            return Err(CliError::IncompleteTransfer(format!(
+                "received {} of {} bytes for {}",
+                received,
+                content_len,
+                partial_file_path.display()
+            )));
+        }
+
+        return Ok(hex::encode(hasher.finalize()));
+    }
+
+    async fn is_complete(file_path: &Path, object: &Object) -> Result<bool> {
+        let size_matches = match tokio::fs::metadata(file_path).await {
+            Ok(metadata) => metadata.is_file() && metadata.len() == object.content_len as u64,
+            Err(_) => false,
+        };
+
+        match checksum::sha256_from_labels(&object.labels) {
+            Some(expected) if size_matches => {
+                Ok(checksum::sha256_file(file_path).await? == expected)
+            }
+            _ => Ok(size_matches),
+        }
+    }
+
+    fn partial_file_path(file_path: &Path) -> PathBuf {
+        let mut file_name = file_path.file_name().unwrap_or_default().to_os_string();
+        file_name.push(PARTIAL_DOWNLOAD_FILE_SUFFIX);
+        file_path.with_file_name(file_name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use crate::{
+        client::{
+            mock::{mock_client, object_groups},
+            paging::DEFAULT_PAGE_SIZE,
+        },
+        util::cli,
+    };
+
+    use super::DownloadHandler;
+
+    #[tokio::test]
+    async fn object_groups_without_revision_are_skipped() {
+        let mut object_groups = object_groups(DEFAULT_PAGE_SIZE as usize + 3);
+        object_groups[1].current_revision = None;
+        object_groups[DEFAULT_PAGE_SIZE as usize + 1].current_revision = None;
+        let (client, _) = mock_client(object_groups, false).await;
+        let (sender, recv) = async_channel::unbounded();
+
+        let skipped = DownloadHandler::handle_resources(
+            cli::Resource::Dataset,
+            "ds".to_string(),
+            Path::new(""),
+            &[],
+            1,
+            client,
+            sender,
+        )
+        .await
+        .unwrap();
+
+        let mut object_group_ids = Vec::new();
+        while let Ok(msg) = recv.try_recv() {
+            object_group_ids.push(msg.object_group_id);
+        }
+        assert_eq!(skipped, 2);
+        assert_eq!(object_group_ids.len(), DEFAULT_PAGE_SIZE as usize + 1);
+        assert!(!object_group_ids.contains(&"og0001".to_string()));
     }
 }
@@ -1,70 +1,265 @@
-use std::path::{Path, PathBuf};
+use std::path::{Component, Path, PathBuf};
 
 use scienceobjectsdb_rust_api::sciobjectsdb::sciobjsdb::api::storage::models::v1::{
     Dataset, Object,
 };
 
+use crate::util::error::{CliError, Result};
+
+use super::download_handler::ObjectDownloadMessage;
+
 const DATASET_DATA_DIR_NAME: &str = "_data";
-const DATASET_INDEX_DIR_NAME: &str = "_index";
+pub const DATASET_INDEX_DIR_NAME: &str = "_index";
+const DATASET_VERSION_DIR_NAME: &str = "_datasetversion";
+const TEMPLATE_MISSING_VALUE: &str = "_";
+const TEMPLATE_PLACEHOLDERS: &[&str] = &[
+    "project_id",
+    "dataset_id",
+    "dataset_name",
+    "dataset_version_id",
+    "object_group_name",
+    "object_id",
+    "filename",
+    "filetype",
+    "label:<key>",
+];
 
 pub trait DownloadPathHandler {
-    fn create_object_group_path(
-        base_path: &Path,
-        msg: &Object,
-        object_group_name: String,
-    ) -> PathBuf;
-    fn dataset_index_path(base_path: &Path, dataset: &Dataset) -> PathBuf;
-    fn create_file_path(base_path: &Path, object: &Object) -> PathBuf;
+    /// The path of the file the object of the message is stored at
+    fn create_file_path(&self, base_path: &Path, msg: &ObjectDownloadMessage) -> PathBuf;
+    fn dataset_index_path(&self, base_path: &Path, dataset: &Dataset) -> PathBuf;
+    fn project_index_path(&self, base_path: &Path, project_id: &str) -> PathBuf;
 }
 
 #[derive(Debug, Clone)]
 pub struct CanonicalDownloadPathHandler {}
 
 impl DownloadPathHandler for CanonicalDownloadPathHandler {
-    fn create_object_group_path(
-        base_path: &Path,
-        object: &Object,
-        object_group_name: String,
-    ) -> PathBuf {
-        let finalpath = base_path
-            .join(object.project_id.clone())
-            .join(object.dataset_id.clone())
-            .join(DATASET_DATA_DIR_NAME)
-            .join(object_group_name);
-        finalpath
-    }
+    fn create_file_path(&self, base_path: &Path, msg: &ObjectDownloadMessage) -> PathBuf {
+        let dataset_path = base_path
+            .join(msg.object.project_id.clone())
+            .join(msg.object.dataset_id.clone());
 
-    fn create_file_path(base_path: &Path, object: &Object) -> PathBuf {
-        let full_file_name = format!("{}.{}", object.filename.clone(), object.filetype.clone());
-        base_path.join(full_file_name)
+        let object_group_path = match &msg.dataset_version_id {
+            Some(dataset_version_id) => dataset_path
+                .join(DATASET_VERSION_DIR_NAME)
+                .join(dataset_version_id)
+                .join(msg.object_group_name.clone()),
+            None => dataset_path
+                .join(DATASET_DATA_DIR_NAME)
+                .join(msg.object_group_name.clone()),
+        };
+
+        object_group_path.join(object_file_name(&msg.object))
     }
 
-    fn dataset_index_path(base_path: &Path, dataset: &Dataset) -> PathBuf {
+    fn dataset_index_path(&self, base_path: &Path, dataset: &Dataset) -> PathBuf {
         base_path
             .join(dataset.project_id.clone())
             .join(dataset.id.clone())
             .join(DATASET_INDEX_DIR_NAME)
     }
+
+    fn project_index_path(&self, base_path: &Path, project_id: &str) -> PathBuf {
+        base_path.join(project_id).join(DATASET_INDEX_DIR_NAME)
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct FlatpathDownloadManager {}
 
 impl DownloadPathHandler for FlatpathDownloadManager {
-    fn create_object_group_path(
-        base_path: &Path,
-        _msg: &Object,
-        object_group_name: String,
-    ) -> PathBuf {
-        base_path.join(object_group_name)
+    fn create_file_path(&self, base_path: &Path, msg: &ObjectDownloadMessage) -> PathBuf {
+        base_path
+            .join(msg.object_group_name.clone())
+            .join(object_file_name(&msg.object))
+    }
+
+    fn dataset_index_path(&self, base_path: &Path, _dataset: &Dataset) -> PathBuf {
+        base_path.join(DATASET_INDEX_DIR_NAME)
+    }
+
+    fn project_index_path(&self, base_path: &Path, _project_id: &str) -> PathBuf {
+        base_path.join(DATASET_INDEX_DIR_NAME)
+    }
+}
+
+/// Builds the paths from a user defined pattern like
+/// `{project_id}/{dataset_name}/{object_group_name}/{label:sample}/{filename}.{filetype}`
+#[derive(Debug, Clone)]
+pub struct TemplateDownloadPathHandler {
+    segments: Vec<TemplateSegment>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum TemplateSegment {
+    Literal(String),
+    ProjectId,
+    DatasetId,
+    DatasetName,
+    DatasetVersionId,
+    ObjectGroupName,
+    ObjectId,
+    Filename,
+    Filetype,
+    Label(String),
+}
+
+impl TemplateDownloadPathHandler {
+    pub fn new(template: &str) -> Result<Self> {
+        let template_path = Path::new(template);
+        if template.trim().is_empty()
+            || template_path.is_absolute()
+            || template_path
+                .components()
+                .any(|x| x == Component::ParentDir)
+        {
+            return Err(CliError::InvalidInput(format!(
+                "path template {:?} has to be a relative path inside of the base path",
+                template
+            )));
+        }
+
+        let mut segments = Vec::new();
+        let mut literal = String::new();
+        let mut chars = template.chars();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '{' => {
+                    let mut placeholder = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some('{') | None => {
+                                return Err(CliError::InvalidInput(format!(
+                                    "unclosed placeholder in path template {:?}",
+                                    template
+                                )))
+                            }
+                            Some(c) => placeholder.push(c),
+                        }
+                    }
+
+                    if !literal.is_empty() {
+                        segments.push(TemplateSegment::Literal(std::mem::take(&mut literal)));
+                    }
+                    segments.push(parse_placeholder(&placeholder)?);
+                }
+                '}' => {
+                    return Err(CliError::InvalidInput(format!(
+                        "unexpected }} in path template {:?}",
+                        template
+                    )))
+                }
+                c => literal.push(c),
+            }
+        }
+
+        if !literal.is_empty() {
+            segments.push(TemplateSegment::Literal(literal));
+        }
+
+        return Ok(TemplateDownloadPathHandler { segments: segments });
     }
+}
 
-    fn dataset_index_path(base_path: &Path, _dataset: &Dataset) -> PathBuf {
+impl DownloadPathHandler for TemplateDownloadPathHandler {
+    fn create_file_path(&self, base_path: &Path, msg: &ObjectDownloadMessage) -> PathBuf {
+        let object = &msg.object;
+        let mut path = String::new();
+        let mut after_dot = false;
+
+        for segment in &self.segments {
+            let value = match segment {
+                TemplateSegment::Literal(literal) => {
+                    path.push_str(literal);
+                    after_dot = literal.ends_with('.');
+                    continue;
+                }
+                TemplateSegment::ProjectId => Some(object.project_id.clone()),
+                TemplateSegment::DatasetId => Some(object.dataset_id.clone()),
+                TemplateSegment::DatasetName => Some(msg.dataset_name.clone()),
+                TemplateSegment::DatasetVersionId => msg.dataset_version_id.clone(),
+                TemplateSegment::ObjectGroupName => Some(msg.object_group_name.clone()),
+                TemplateSegment::ObjectId => Some(object.id.clone()),
+                TemplateSegment::Filename => Some(object.filename.clone()),
+                TemplateSegment::Filetype => Some(object.filetype.clone()),
+                // labels of the object take precedence over the labels of its object group
+                TemplateSegment::Label(key) => object
+                    .labels
+                    .iter()
+                    .chain(msg.object_group_labels.iter())
+                    .find(|label| &label.key == key)
+                    .map(|label| label.value.clone()),
+            };
+
+            let value = sanitize_path_value(value);
+            // `{filename}.{filetype}` of an object without a file type has no trailing dot
+            if value.is_empty() && after_dot {
+                path.pop();
+            }
+            path.push_str(&value);
+            after_dot = false;
+        }
+
+        // placeholders without a value must not remove a directory or make the path absolute
+        let path = path
+            .split('/')
+            .map(|x| match x {
+                "" => TEMPLATE_MISSING_VALUE,
+                x => x,
+            })
+            .collect::<Vec<&str>>()
+            .join("/");
+
+        base_path.join(path)
+    }
+
+    fn dataset_index_path(&self, base_path: &Path, _dataset: &Dataset) -> PathBuf {
         base_path.join(DATASET_INDEX_DIR_NAME)
     }
 
-    fn create_file_path(base_path: &Path, object: &Object) -> PathBuf {
-        let full_file_name = format!("{}.{}", object.filename.clone(), object.filetype.clone());
-        base_path.join(full_file_name)
+    fn project_index_path(&self, base_path: &Path, _project_id: &str) -> PathBuf {
+        base_path.join(DATASET_INDEX_DIR_NAME)
+    }
+}
+
+fn parse_placeholder(placeholder: &str) -> Result<TemplateSegment> {
+    let segment = match placeholder.trim() {
+        "project_id" => TemplateSegment::ProjectId,
+        "dataset_id" => TemplateSegment::DatasetId,
+        "dataset_name" => TemplateSegment::DatasetName,
+        "dataset_version_id" => TemplateSegment::DatasetVersionId,
+        "object_group_name" => TemplateSegment::ObjectGroupName,
+        "object_id" => TemplateSegment::ObjectId,
+        "filename" => TemplateSegment::Filename,
+        "filetype" => TemplateSegment::Filetype,
+        placeholder => match placeholder.strip_prefix("label:") {
+            Some(key) if !key.trim().is_empty() => TemplateSegment::Label(key.trim().to_string()),
+            _ => {
+                return Err(CliError::InvalidInput(format!(
+                    "unknown placeholder {{{}}} in path template, valid placeholders are: {}",
+                    placeholder,
+                    TEMPLATE_PLACEHOLDERS.join(", ")
+                )))
+            }
+        },
+    };
+
+    return Ok(segment);
+}
+
+/// Values of placeholders must not add directories or leave the base path, missing values
+/// are empty
+fn sanitize_path_value(value: Option<String>) -> String {
+    match value {
+        None => String::new(),
+        Some(value) if value == "." || value == ".." => TEMPLATE_MISSING_VALUE.to_string(),
+        Some(value) => value.replace(['/', '\\'], "_"),
     }
 }
+
+fn object_file_name(object: &Object) -> String {
+    format!("{}.{}", object.filename.clone(), object.filetype.clone())
+}
@@ -1,2 +1,5 @@
+pub mod archive;
+pub mod dataset_index;
 pub mod download_handler;
 pub mod download_path_handler;
+pub mod path_claims;
@@ -0,0 +1,146 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use scienceobjectsdb_rust_api::sciobjectsdb::sciobjsdb::api::storage::models::v1::Object;
+
+use crate::util::{
+    cli::CollisionPolicy,
+    error::{CliError, Result},
+};
+
+/// Keeps track of the paths used by the objects of a download, so that objects with the same
+/// path do not overwrite each other. Shared by all download workers
+pub struct PathClaims {
+    policy: CollisionPolicy,
+    claims: Mutex<HashMap<PathBuf, String>>,
+    /// The object with the smallest id of every reserved path
+    owners: Mutex<HashMap<PathBuf, String>>,
+    conflicts: Mutex<Vec<String>>,
+}
+
+impl PathClaims {
+    pub fn new(policy: CollisionPolicy) -> Self {
+        return PathClaims {
+            policy: policy,
+            claims: Mutex::new(HashMap::new()),
+            owners: Mutex::new(HashMap::new()),
+            conflicts: Mutex::new(Vec::new()),
+        };
+    }
+
+    /// Whether all paths have to be reserved before the first claim. The policies that load
+    /// some of the colliding objects would otherwise depend on the order of the workers
+    pub fn needs_reservations(&self) -> bool {
+        return self.policy != CollisionPolicy::Fail;
+    }
+
+    /// Registers the path of an object before any object is claimed. A path that is reserved
+    /// by several objects belongs to the object with the smallest id, so that every run
+    /// resolves the collision the same way
+    pub fn reserve(&self, path: PathBuf, object: &Object) {
+        let mut owners = match self.owners.lock() {
+            Ok(owners) => owners,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+
+        let owner = owners.entry(path).or_insert_with(|| object.id.clone());
+        if object.id < *owner {
+            *owner = object.id.clone();
+        }
+    }
+
+    /// Returns the path the object should be stored at, or None if the object is skipped
+    pub fn claim(&self, path: PathBuf, object: &Object) -> Option<PathBuf> {
+        let mut claims = match self.claims.lock() {
+            Ok(claims) => claims,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        let owners = match self.owners.lock() {
+            Ok(owners) => owners,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+
+        // without a reservation the path belongs to the object that claims it first
+        let owner = match owners.get(&path).or_else(|| claims.get(&path)) {
+            Some(owner) => owner.clone(),
+            None => object.id.clone(),
+        };
+        if owner == object.id {
+            return match claims.insert(path.clone(), object.id.clone()) {
+                // the same object can be part of several object groups of a dataset version
+                Some(_) => None,
+                None => Some(path),
+            };
+        }
+
+        let (target, action) = match self.policy {
+            CollisionPolicy::Fail => (None, "not loaded".to_string()),
+            CollisionPolicy::Skip => (None, "skipped".to_string()),
+            CollisionPolicy::Suffix => {
+                let suffixed_path = suffixed_path(&path, &object.id);
+                if claims.get(&suffixed_path) == Some(&object.id) {
+                    return None;
+                }
+                match claims.contains_key(&suffixed_path) || owners.contains_key(&suffixed_path) {
+                    true => (None, "skipped".to_string()),
+                    false => {
+                        claims.insert(suffixed_path.clone(), object.id.clone());
+                        let action = format!("stored as {}", suffixed_path.display());
+                        (Some(suffixed_path), action)
+                    }
+                }
+            }
+        };
+
+        let conflict = format!(
+            "{} is used by object {} and object {}, {} was {}",
+            path.display(),
+            owner,
+            object.id,
+            object.id,
+            action
+        );
+        eprintln!("path collision: {}", conflict);
+        match self.conflicts.lock() {
+            Ok(mut conflicts) => conflicts.push(conflict),
+            Err(poisoned) => poisoned.into_inner().push(conflict),
+        }
+
+        return target;
+    }
+
+    /// Fails if conflicts occurred and the policy does not resolve them
+    pub fn finish(&self) -> Result<()> {
+        let conflicts = match self.conflicts.lock() {
+            Ok(conflicts) => conflicts,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+
+        if self.policy != CollisionPolicy::Fail || conflicts.is_empty() {
+            return Ok(());
+        }
+
+        return Err(CliError::PathCollision(format!(
+            "{} object(s) share their path with another object, use --on-collision suffix or \
+             skip to load them anyway:\n  {}",
+            conflicts.len(),
+            conflicts.join("\n  ")
+        )));
+    }
+}
+
+/// Adds the object id to the file name, e.g. `reads.fastq` becomes `reads_<id>.fastq`
+fn suffixed_path(path: &Path, object_id: &str) -> PathBuf {
+    let mut file_name = path.file_stem().unwrap_or_default().to_os_string();
+    file_name.push("_");
+    file_name.push(object_id);
+    if let Some(extension) = path.extension() {
+        file_name.push(".");
+        file_name.push(extension);
+    }
+
+    path.with_file_name(file_name)
+}
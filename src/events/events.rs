@@ -12,6 +12,7 @@ use tonic::Request;
 
 use crate::client::client;
 use crate::util;
+use crate::util::error::{CliError, Result};
 
 pub struct Events {
     client: client::Client,
@@ -22,7 +23,10 @@ impl Events {
         return Events { client: client };
     }
 
-    pub async fn create_stream_consumer(&mut self, request: util::cli::CreateStreamConsumer) {
+    pub async fn create_stream_consumer(
+        &mut self,
+        request: util::cli::CreateStreamConsumer,
+    ) -> Result<()> {
         let proto_resource = match request.resource {
             util::cli::Resource::Project => {
                 create_event_streaming_group_request::EventResources::ProjectResource
@@ -36,6 +40,11 @@ impl Events {
             util::cli::Resource::ObjectGroup => {
                 create_event_streaming_group_request::EventResources::ObjectGroupResource
             }
+            util::cli::Resource::Object => {
+                return Err(CliError::InvalidInput(
+                    "event streams are not available for single objects".to_string(),
+                ))
+            }
         };
 
         let request = CreateEventStreamingGroupRequest {
@@ -50,14 +59,15 @@ impl Events {
             .client
             .notification_service
             .create_event_streaming_group(request)
-            .await
-            .unwrap()
+            .await?
             .into_inner();
 
-        println!("ConsumerGroup ID: {:#?}", response.stream_group_id)
+        println!("ConsumerGroup ID: {:#?}", response.stream_group_id);
+
+        Ok(())
     }
 
-    pub async fn stream_events(&mut self, request: util::cli::Stream) {
+    pub async fn stream_events(&mut self, request: util::cli::Stream) -> Result<()> {
         let (send, recv) = async_channel::bounded(3);
 
         let outbound = async_stream::stream! {
@@ -71,7 +81,11 @@ impl Events {
             yield init_request;
 
             loop {
-                let ack_chunk_id = recv.recv().await.unwrap();
+                // the channel is closed once the notification stream has ended
+                let ack_chunk_id = match recv.recv().await {
+                    Ok(ack_chunk_id) => ack_chunk_id,
+                    Err(_) => break,
+                };
                 let ack_request = NotificationStreamGroupRequest {
                     close: false,
                     stream_action: Some(Ack(NotficationStreamAck {
@@ -87,16 +101,17 @@ impl Events {
             .client
             .notification_service
             .notification_stream_group(Request::new(outbound))
-            .await
-            .unwrap();
+            .await?;
         let mut notification_stream = response.into_inner();
         while let Some(notification_result) = notification_stream.next().await {
-            let notification = notification_result.unwrap();
+            let notification = notification_result?;
             for event in notification.notification {
                 println!("{:?}", event)
             }
 
-            send.send(notification.ack_chunk_id).await.unwrap();
+            send.send(notification.ack_chunk_id).await?;
         }
+
+        Ok(())
     }
 }
@@ -1,106 +1,156 @@
-use crate::client::client;
+use crate::client::{client, paging};
+use crate::output::output::OutputWriter;
+use crate::output::views::{
+    DatasetVersionView, DatasetView, ObjectGroupRevisionView, ObjectGroupView, ObjectView,
+};
 use crate::util::cli;
+use crate::util::error::{CliError, Result};
 
+use futures::TryStreamExt;
 use scienceobjectsdb_rust_api::sciobjectsdb::sciobjsdb::api::storage::services::v1::{
-    GetDatasetObjectGroupsRequest, GetDatasetObjectsRequest, GetDatasetVersionObjectGroupsRequest,
     GetDatasetVersionsRequest, GetProjectDatasetsRequest,
 };
 
 pub struct LS {
     client: client::Client,
+    output: OutputWriter,
 }
 
 impl LS {
-    pub fn new(client: client::Client) -> Self {
-        return LS { client: client };
+    pub fn new(client: client::Client, output: OutputWriter) -> Self {
+        return LS {
+            client: client,
+            output: output,
+        };
     }
 
-    pub async fn ls(&mut self, request: cli::Ls) {
+    pub async fn ls(&mut self, request: cli::Ls) -> Result<()> {
+        let id = request.id.clone().ok_or_else(|| {
+            CliError::InvalidInput("the id of the parent resource is required".to_string())
+        })?;
+
+        // only objects and object groups of datasets are filtered by the server, the labels of
+        // the other resources are checked here
         match request.resource {
-            cli::LsResource::ProjectDatasets => self.ls_project_dataset(request).await,
-            cli::LsResource::DatasetObjectGroups => self.ls_dataset_object_groups(request).await,
-            cli::LsResource::DatasetVersions => self.ls_dataset_versions(request).await,
+            cli::LsResource::ProjectDatasets => self.ls_project_dataset(id, request).await,
+            cli::LsResource::DatasetObjectGroups => {
+                self.ls_dataset_object_groups(id, request).await
+            }
+            cli::LsResource::DatasetVersions => self.ls_dataset_versions(id, request).await,
             cli::LsResource::DatasetVersionObjectGroups => {
-                self.ls_dataset_version_object_groups(request).await
+                self.ls_dataset_version_object_groups(id, request).await
             }
-            cli::LsResource::DatasetObjects => self.ls_dataset_objects(request).await,
+            cli::LsResource::DatasetObjects => self.ls_dataset_objects(id, request).await,
         }
     }
 
-    async fn ls_project_dataset(&mut self, request: cli::Ls) {
+    async fn ls_project_dataset(&mut self, id: String, request: cli::Ls) -> Result<()> {
         let datasets = self
             .client
-            .project_service
-            .get_project_datasets(GetProjectDatasetsRequest { id: request.id })
-            .await
-            .unwrap()
-            .into_inner();
+            .call(
+                &self.client.project_service,
+                GetProjectDatasetsRequest { id: id },
+                |mut service, req| async move { service.get_project_datasets(req).await },
+            )
+            .await?;
 
-        println!("{:#?}", datasets.datasets)
+        let datasets: Vec<DatasetView> = datasets
+            .datasets
+            .iter()
+            .filter(|x| cli::matches_labels(&request.labels, &x.labels))
+            .map(DatasetView::from)
+            .collect();
+        self.output.write_list(&datasets)
     }
 
-    async fn ls_dataset_object_groups(&mut self, request: cli::Ls) {
-        let object_groups = self
-            .client
-            .dataset_service
-            .get_dataset_object_groups(GetDatasetObjectGroupsRequest {
-                id: request.id,
-                page_request: None,
-                label_filter: None,
-            })
-            .await
-            .unwrap()
-            .into_inner();
-
-        println!("{:#?}", object_groups.object_groups)
+    async fn ls_dataset_object_groups(&mut self, id: String, request: cli::Ls) -> Result<()> {
+        let pages = paging::dataset_object_group_pages(
+            self.client.clone(),
+            id,
+            request.labels.clone(),
+            request.page_size,
+            request.after.clone().unwrap_or_default(),
+        );
+        futures::pin_mut!(pages);
+
+        let mut writer = self.output.page_writer();
+        while let Some(object_groups) = pages.try_next().await? {
+            writer.write_page(object_groups.iter().map(ObjectGroupView::from).collect())?;
+            if !request.all {
+                break;
+            }
+        }
+
+        writer.finish()
     }
 
-    async fn ls_dataset_versions(&mut self, request: cli::Ls) {
+    async fn ls_dataset_versions(&mut self, id: String, request: cli::Ls) -> Result<()> {
         let dataset_versions = self
             .client
-            .dataset_service
-            .get_dataset_versions(GetDatasetVersionsRequest { id: request.id })
-            .await
-            .unwrap()
-            .into_inner();
+            .call(
+                &self.client.dataset_service,
+                GetDatasetVersionsRequest { id: id },
+                |mut service, req| async move { service.get_dataset_versions(req).await },
+            )
+            .await?;
 
-        println!("{:#?}", dataset_versions.dataset_versions)
+        let dataset_versions: Vec<DatasetVersionView> = dataset_versions
+            .dataset_versions
+            .iter()
+            .filter(|x| cli::matches_labels(&request.labels, &x.labels))
+            .map(DatasetVersionView::from)
+            .collect();
+        self.output.write_list(&dataset_versions)
     }
 
-    async fn ls_dataset_version_object_groups(&mut self, request: cli::Ls) {
-        let object_groups = self
-            .client
-            .dataset_service
-            .get_dataset_version_object_groups(GetDatasetVersionObjectGroupsRequest {
-                id: request.id,
-                page_request: None,
-            })
-            .await
-            .unwrap()
-            .into_inner();
-
-        println!("{:#?}", object_groups.object_group_revisions)
+    async fn ls_dataset_version_object_groups(
+        &mut self,
+        id: String,
+        request: cli::Ls,
+    ) -> Result<()> {
+        let pages = paging::dataset_version_object_group_pages(
+            self.client.clone(),
+            id,
+            request.page_size,
+            request.after.clone().unwrap_or_default(),
+        );
+        futures::pin_mut!(pages);
+
+        let mut writer = self.output.page_writer();
+        while let Some(object_group_revisions) = pages.try_next().await? {
+            writer.write_page(
+                object_group_revisions
+                    .iter()
+                    .filter(|x| cli::matches_labels(&request.labels, &x.labels))
+                    .map(ObjectGroupRevisionView::from)
+                    .collect(),
+            )?;
+            if !request.all {
+                break;
+            }
+        }
+
+        writer.finish()
     }
-    async fn ls_dataset_objects(&mut self, request: cli::Ls) {
-        let objects = self
-            .client
-            .dataset_service
-            .get_dataset_objects(GetDatasetObjectsRequest {
-                id: request.id,
-                page_request: None,
-                label_filter: None,
-            })
-            .await
-            .unwrap()
-            .into_inner();
-        println!("{:#?}", objects);
-        // println!(
-        //     "IDs: {:#?}",
-        //     objects
-        //         .objects
-        //         .into_iter()
-        //         .map(|x| x.id)
-        //         .collect::<Vec<String>>()
-        // )
+
+    async fn ls_dataset_objects(&mut self, id: String, request: cli::Ls) -> Result<()> {
+        let pages = paging::dataset_object_pages(
+            self.client.clone(),
+            id,
+            cli::label_filter(&request.labels),
+            request.page_size,
+            request.after.clone().unwrap_or_default(),
+        );
+        futures::pin_mut!(pages);
+
+        let mut writer = self.output.page_writer();
+        while let Some(objects) = pages.try_next().await? {
+            writer.write_page(objects.iter().map(ObjectView::from).collect())?;
+            if !request.all {
+                break;
+            }
+        }
+
+        writer.finish()
     }
 }
@@ -1,13 +1,16 @@
 #![feature(generators, generator_trait)]
 
+mod cat;
 mod client;
 mod create;
 mod describe;
 mod download;
 mod events;
 mod ls;
+mod output;
 mod update;
 mod util;
+mod verify;
 
 use clap::{Parser, Subcommand};
 use tonic::transport::ClientTlsConfig;
@@ -15,14 +18,30 @@ use tonic::transport::ClientTlsConfig;
 
 use crate::download::download_path_handler::CanonicalDownloadPathHandler;
 use crate::download::download_path_handler::FlatpathDownloadManager;
+use crate::download::download_path_handler::TemplateDownloadPathHandler;
 
 #[derive(Parser)]
 #[clap(subcommand_required = true, arg_required_else_help = true)]
 #[clap(about, version, author)]
 struct Cli {
-    #[clap(short)]
+    #[clap(short, env = util::config::CONFIG_ENV_VAR)]
     /// Path to the config file. Defaults to: .config/sciobjsdbcli/config.yaml or .sciobjsdb/config.yaml
     config: Option<String>,
+    /// Profile of the config file, defaults to the default_profile of the config file
+    #[clap(long, global = true, env = util::config::PROFILE_ENV_VAR)]
+    profile: Option<String>,
+    /// Endpoint of the API, takes precedence over the config file
+    #[clap(long, global = true, env = util::config::ENDPOINT_ENV_VAR)]
+    endpoint: Option<String>,
+    /// API key, takes precedence over the config file
+    #[clap(long, global = true, env = util::config::API_KEY_ENV_VAR, hide_env_values = true)]
+    api_key: Option<String>,
+    /// Output format of listed and described resources
+    #[clap(arg_enum, long, global = true, default_value = "table")]
+    output: util::cli::OutputFormat,
+    /// Limits the combined rate of all uploads and downloads in bytes per second, e.g. 50M
+    #[clap(long, global = true, parse(try_from_str = util::cli::parse_byte_size))]
+    limit_rate: Option<usize>,
     #[clap(subcommand)]
     command: Commands,
 }
@@ -40,12 +59,22 @@ enum Commands {
     /// Creates the given resource type from the given file
     Create(util::cli::CreateRequest),
     /// Loads a given resource to disk
-    /// There are two possible directory structures {n}\
+    /// There are three possible directory structures {n}\
     /// 1. Canonical (Default)
     ///   The canonical structure is based on the internal structure of the stored data, so the structure will always be
     ///   /<project_id>/<dataset_id>/_data/<object_group_name>/<object_name>. Datasetversions will be stored under
-    ///   /<project_id>/<dataset_id>/_datasetversion/<object_group_name>/<object_name>
+    ///   /<project_id>/<dataset_id>/_datasetversion/<dataset_version_id>/<object_group_name>/<object_name>
+    /// 2. Flat
+    ///   /<object_group_name>/<object_name>
+    /// 3. Template
+    ///   The paths are built from the pattern given with --path-template
+    /// Single objects are stored at the given path, or inside of it if the path is a directory
     Load(util::cli::Load),
+    /// Writes the data of a single object to stdout
+    Cat(util::cli::Cat),
+    /// Compares a loaded resource on disk with the remote resource and reports missing, extra
+    /// and modified files. Uses the same directory structure as load
+    Verify(util::cli::Verify),
     /// Updates Objects inside the ObjectGroup
     Update(util::cli::UpdateRequest),
 }
@@ -56,58 +85,144 @@ async fn main() {
 
     let cli = Cli::parse();
 
+    if let Err(err) = run(cli).await {
+        eprintln!("error: {}", err);
+        std::process::exit(err.exit_code());
+    }
+}
+
+async fn run(cli: Cli) -> util::error::Result<()> {
     let tls_config = ClientTlsConfig::new();
 
+    let overrides = util::config::ConfigOverrides {
+        endpoint: cli.endpoint,
+        api_key: cli.api_key,
+    };
     let config = match cli.config {
-        Some(config) => util::config::Config::specified_path(config.as_str()).await,
-        None => util::config::Config::new().await,
+        Some(config) => {
+            util::config::Config::specified_path(config.as_str(), cli.profile.as_deref(), overrides)
+                .await?
+        }
+        None => util::config::Config::new(cli.profile.as_deref(), overrides).await?,
     };
-    let mut endpoint = tonic::transport::Channel::from_shared(config.endpoint).unwrap();
-    endpoint = endpoint.tls_config(tls_config).unwrap();
+    let mut endpoint =
+        tonic::transport::Channel::from_shared(config.endpoint.clone()).map_err(|_| {
+            util::error::CliError::Config(format!("invalid endpoint {:?}", config.endpoint))
+        })?;
+    endpoint = endpoint.tls_config(tls_config)?;
 
-    let channel = endpoint.connect().await.unwrap();
-    let client = client::client::Client::new(channel, config.api_key.clone()).await;
+    let channel = endpoint.connect().await?;
+    let retry_policy = client::retry::RetryPolicy::from_config(&config.retry)?;
+    let rate_limiter =
+        client::rate_limit::RateLimiter::new(cli.limit_rate.or(config.transfer.limit_rate));
+    let client =
+        client::client::Client::new(channel, config.api_key.clone(), retry_policy, rate_limiter)
+            .await?;
+    let output = output::output::OutputWriter::new(cli.output);
 
     match cli.command {
-        Commands::Ls(request) => {
-            let mut ls = ls::ls::LS::new(client.clone());
-            ls.ls(request).await;
+        Commands::Ls(mut request) => {
+            if request.resource == util::cli::LsResource::ProjectDatasets {
+                request.id = request.id.or_else(|| config.project_id.clone());
+            }
+            let mut ls = ls::ls::LS::new(client.clone(), output);
+            ls.ls(request).await?;
         }
-        Commands::Describe(request) => {
-            let mut describe = describe::describe::Describe::new(client.clone());
-            describe.describe(request).await;
+        Commands::Describe(mut request) => {
+            if request.resource == util::cli::Resource::Project {
+                request.id = request.id.or_else(|| config.project_id.clone());
+            }
+            let mut describe = describe::describe::Describe::new(client.clone(), output);
+            describe.describe(request).await?;
         }
         Commands::EventStream(request) => {
             let mut stream = events::events::Events::new(client.clone());
-            stream.stream_events(request).await;
+            stream.stream_events(request).await?;
         }
-        Commands::Create(request) => {
-            let mut create = create::create::Create::new(client.clone());
-            create.create(request).await;
+        Commands::Create(mut request) => {
+            request.apply_profile(&config.transfer);
+            let mut create =
+                create::create::Create::new(client.clone(), config.project_id.clone())?;
+            create.create(request).await?;
         }
-        Commands::Load(request) => match request.path_style {
-            util::cli::DownloadPathStyle::Canonical => {
-                download::download_handler::DownloadHandler::download::<CanonicalDownloadPathHandler>(
-                    request,
-                    client.clone(),
-                )
-                .await
+        Commands::Load(mut request) => {
+            request.apply_profile(&config.transfer);
+            if request.resource == util::cli::Resource::Project {
+                request.id = request.id.or_else(|| config.project_id.clone());
             }
-            util::cli::DownloadPathStyle::Flat => {
-                download::download_handler::DownloadHandler::download::<FlatpathDownloadManager>(
-                    request,
-                    client.clone(),
-                )
-                .await
+            match request.path_style {
+                util::cli::DownloadPathStyle::Canonical => {
+                    download::download_handler::DownloadHandler::download(
+                        request,
+                        client.clone(),
+                        CanonicalDownloadPathHandler {},
+                    )
+                    .await?
+                }
+                util::cli::DownloadPathStyle::Flat => {
+                    download::download_handler::DownloadHandler::download(
+                        request,
+                        client.clone(),
+                        FlatpathDownloadManager {},
+                    )
+                    .await?
+                }
+                util::cli::DownloadPathStyle::Template => {
+                    let path_handler = template_path_handler(&request.path_template)?;
+                    download::download_handler::DownloadHandler::download(
+                        request,
+                        client.clone(),
+                        path_handler,
+                    )
+                    .await?
+                }
             }
-        },
+        }
+        Commands::Cat(request) => {
+            let cat = cat::cat::Cat::new(client.clone());
+            cat.cat(request).await?;
+        }
+        Commands::Verify(mut request) => {
+            request.apply_profile(&config.transfer);
+            if request.resource == util::cli::Resource::Project {
+                request.id = request.id.or_else(|| config.project_id.clone());
+            }
+            let verify = verify::verify::Verify::new(client.clone(), output);
+            match request.path_style {
+                util::cli::DownloadPathStyle::Canonical => {
+                    verify
+                        .verify(request, CanonicalDownloadPathHandler {})
+                        .await?
+                }
+                util::cli::DownloadPathStyle::Flat => {
+                    verify.verify(request, FlatpathDownloadManager {}).await?
+                }
+                util::cli::DownloadPathStyle::Template => {
+                    let path_handler = template_path_handler(&request.path_template)?;
+                    verify.verify(request, path_handler).await?
+                }
+            }
+        }
         Commands::CreateStreamConsumer(request) => {
             let mut stream = events::events::Events::new(client.clone());
-            stream.create_stream_consumer(request).await;
-        },
+            stream.create_stream_consumer(request).await?;
+        }
         Commands::Update(request) => {
             let mut update = update::update::Update::new(client.clone());
-            update.update(request).await;
+            update.update(request).await?;
         }
     };
+
+    Ok(())
+}
+
+fn template_path_handler(
+    path_template: &Option<String>,
+) -> util::error::Result<TemplateDownloadPathHandler> {
+    match path_template {
+        Some(path_template) => TemplateDownloadPathHandler::new(path_template),
+        None => Err(util::error::CliError::InvalidInput(
+            "--path-template is required for the template path style".to_string(),
+        )),
+    }
 }
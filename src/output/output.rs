@@ -0,0 +1,120 @@
+use serde::Serialize;
+
+use crate::util::{cli::OutputFormat, error::Result};
+
+use super::views::TableRow;
+
+const TABLE_COLUMN_SEPARATOR: &str = "  ";
+
+/// Prints resources in the output format selected with `--output`
+#[derive(Clone, Copy)]
+pub struct OutputWriter {
+    format: OutputFormat,
+}
+
+impl OutputWriter {
+    pub fn new(format: OutputFormat) -> Self {
+        return OutputWriter { format: format };
+    }
+
+    pub fn write_list<T: Serialize + TableRow>(&self, entries: &[T]) -> Result<()> {
+        match self.format {
+            OutputFormat::Json => println!("{}", serde_json::to_string_pretty(entries)?),
+            OutputFormat::Yaml => print!("{}", serde_yaml::to_string(entries)?),
+            OutputFormat::Table => print!("{}", format_table(entries, true)),
+            OutputFormat::Ids => {
+                for entry in entries {
+                    println!("{}", entry.id())
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Creates a writer for lists that are received page by page
+    pub fn page_writer<T: Serialize + TableRow>(&self) -> PageWriter<T> {
+        return PageWriter {
+            output: *self,
+            entries: Vec::new(),
+            header_written: false,
+        };
+    }
+
+    pub fn write_single<T: Serialize + TableRow>(&self, entry: &T) -> Result<()> {
+        match self.format {
+            OutputFormat::Json => println!("{}", serde_json::to_string_pretty(entry)?),
+            OutputFormat::Yaml => print!("{}", serde_yaml::to_string(entry)?),
+            OutputFormat::Table | OutputFormat::Ids => {
+                return self.write_list(std::slice::from_ref(entry))
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Table and id output is streamed as the pages arrive, json and yaml output is collected
+/// and written once in [`PageWriter::finish`] to form a single valid document
+pub struct PageWriter<T: Serialize + TableRow> {
+    output: OutputWriter,
+    entries: Vec<T>,
+    header_written: bool,
+}
+
+impl<T: Serialize + TableRow> PageWriter<T> {
+    pub fn write_page(&mut self, mut page: Vec<T>) -> Result<()> {
+        match self.output.format {
+            OutputFormat::Json | OutputFormat::Yaml => self.entries.append(&mut page),
+            OutputFormat::Table => {
+                print!("{}", format_table(&page, !self.header_written));
+                self.header_written = true;
+            }
+            OutputFormat::Ids => self.output.write_list(&page)?,
+        }
+
+        Ok(())
+    }
+
+    pub fn finish(self) -> Result<()> {
+        match self.output.format {
+            OutputFormat::Json | OutputFormat::Yaml => self.output.write_list(&self.entries)?,
+            OutputFormat::Table if !self.header_written => {
+                print!("{}", format_table::<T>(&[], true))
+            }
+            OutputFormat::Table | OutputFormat::Ids => (),
+        }
+
+        Ok(())
+    }
+}
+
+fn format_table<T: TableRow>(entries: &[T], with_header: bool) -> String {
+    let header: Vec<String> = T::header().into_iter().map(|x| x.to_string()).collect();
+    let rows: Vec<Vec<String>> = entries.iter().map(|x| x.row()).collect();
+
+    let mut widths: Vec<usize> = header.iter().map(|x| x.chars().count()).collect();
+    for row in &rows {
+        for (i, column) in row.iter().enumerate() {
+            widths[i] = std::cmp::max(widths[i], column.chars().count());
+        }
+    }
+
+    let mut table = String::new();
+    let header_row = match with_header {
+        true => Some(&header),
+        false => None,
+    };
+
+    for row in header_row.into_iter().chain(rows.iter()) {
+        let line: Vec<String> = row
+            .iter()
+            .enumerate()
+            .map(|(i, column)| format!("{:width$}", column, width = widths[i]))
+            .collect();
+        table.push_str(line.join(TABLE_COLUMN_SEPARATOR).trim_end());
+        table.push('\n');
+    }
+
+    return table;
+}
@@ -0,0 +1,383 @@
+use scienceobjectsdb_rust_api::sciobjectsdb::sciobjsdb::api::storage::models::v1::{
+    Dataset, DatasetVersion, Label, Object, ObjectGroup, ObjectGroupRevision, Project,
+};
+use serde::Serialize;
+
+/// Resources that can be rendered as a row of the table output
+pub trait TableRow {
+    fn header() -> Vec<&'static str>;
+    fn row(&self) -> Vec<String>;
+    fn id(&self) -> String;
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct LabelView {
+    pub key: String,
+    pub value: String,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct ProjectView {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub labels: Vec<LabelView>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct DatasetView {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub project_id: String,
+    pub labels: Vec<LabelView>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct DatasetVersionView {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub dataset_id: String,
+    pub labels: Vec<LabelView>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct ObjectGroupView {
+    pub id: String,
+    pub name: String,
+    pub dataset_id: String,
+    pub labels: Vec<LabelView>,
+    pub current_revision: Option<ObjectGroupRevisionView>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct ObjectGroupRevisionView {
+    pub id: String,
+    pub object_group_id: String,
+    pub name: String,
+    pub description: String,
+    pub revision: i64,
+    pub labels: Vec<LabelView>,
+    pub objects: Vec<ObjectView>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct ObjectView {
+    pub id: String,
+    pub filename: String,
+    pub filetype: String,
+    pub content_len: i64,
+    pub project_id: String,
+    pub dataset_id: String,
+    pub labels: Vec<LabelView>,
+}
+
+pub fn label_views(labels: &[Label]) -> Vec<LabelView> {
+    labels
+        .iter()
+        .map(|label| LabelView {
+            key: label.key.clone(),
+            value: label.value.clone(),
+        })
+        .collect()
+}
+
+fn format_labels(labels: &[LabelView]) -> String {
+    labels
+        .iter()
+        .map(|label| format!("{}={}", label.key, label.value))
+        .collect::<Vec<String>>()
+        .join(",")
+}
+
+impl From<&Project> for ProjectView {
+    fn from(project: &Project) -> Self {
+        ProjectView {
+            id: project.id.clone(),
+            name: project.name.clone(),
+            description: project.description.clone(),
+            labels: label_views(&project.labels),
+        }
+    }
+}
+
+impl From<&Dataset> for DatasetView {
+    fn from(dataset: &Dataset) -> Self {
+        DatasetView {
+            id: dataset.id.clone(),
+            name: dataset.name.clone(),
+            description: dataset.description.clone(),
+            project_id: dataset.project_id.clone(),
+            labels: label_views(&dataset.labels),
+        }
+    }
+}
+
+impl From<&DatasetVersion> for DatasetVersionView {
+    fn from(dataset_version: &DatasetVersion) -> Self {
+        DatasetVersionView {
+            id: dataset_version.id.clone(),
+            name: dataset_version.name.clone(),
+            description: dataset_version.description.clone(),
+            dataset_id: dataset_version.dataset_id.clone(),
+            labels: label_views(&dataset_version.labels),
+        }
+    }
+}
+
+// name and labels of an object group belong to its revisions
+impl From<&ObjectGroup> for ObjectGroupView {
+    fn from(object_group: &ObjectGroup) -> Self {
+        let (name, labels) = match &object_group.current_revision {
+            Some(revision) => (revision.name.clone(), label_views(&revision.labels)),
+            None => ("".to_string(), Vec::new()),
+        };
+
+        ObjectGroupView {
+            id: object_group.id.clone(),
+            name: name,
+            dataset_id: object_group.dataset_id.clone(),
+            labels: labels,
+            current_revision: object_group
+                .current_revision
+                .as_ref()
+                .map(ObjectGroupRevisionView::from),
+        }
+    }
+}
+
+impl From<&ObjectGroupRevision> for ObjectGroupRevisionView {
+    fn from(revision: &ObjectGroupRevision) -> Self {
+        ObjectGroupRevisionView {
+            id: revision.id.clone(),
+            object_group_id: revision.object_group_id.clone(),
+            name: revision.name.clone(),
+            description: revision.description.clone(),
+            revision: revision.revision_number,
+            labels: label_views(&revision.labels),
+            objects: revision.objects.iter().map(ObjectView::from).collect(),
+        }
+    }
+}
+
+impl From<&Object> for ObjectView {
+    fn from(object: &Object) -> Self {
+        ObjectView {
+            id: object.id.clone(),
+            filename: object.filename.clone(),
+            filetype: object.filetype.clone(),
+            content_len: object.content_len,
+            project_id: object.project_id.clone(),
+            dataset_id: object.dataset_id.clone(),
+            labels: label_views(&object.labels),
+        }
+    }
+}
+
+impl TableRow for ProjectView {
+    fn header() -> Vec<&'static str> {
+        vec!["ID", "NAME", "DESCRIPTION"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        vec![self.id.clone(), self.name.clone(), self.description.clone()]
+    }
+
+    fn id(&self) -> String {
+        self.id.clone()
+    }
+}
+
+impl TableRow for DatasetView {
+    fn header() -> Vec<&'static str> {
+        vec!["ID", "NAME", "PROJECT ID", "LABELS"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.id.clone(),
+            self.name.clone(),
+            self.project_id.clone(),
+            format_labels(&self.labels),
+        ]
+    }
+
+    fn id(&self) -> String {
+        self.id.clone()
+    }
+}
+
+impl TableRow for DatasetVersionView {
+    fn header() -> Vec<&'static str> {
+        vec!["ID", "NAME", "DATASET ID", "LABELS"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.id.clone(),
+            self.name.clone(),
+            self.dataset_id.clone(),
+            format_labels(&self.labels),
+        ]
+    }
+
+    fn id(&self) -> String {
+        self.id.clone()
+    }
+}
+
+impl TableRow for ObjectGroupView {
+    fn header() -> Vec<&'static str> {
+        vec!["ID", "NAME", "REVISION ID", "OBJECTS", "LABELS"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        let (revision_id, object_count) = match &self.current_revision {
+            Some(revision) => (revision.id.clone(), revision.objects.len().to_string()),
+            None => ("".to_string(), "".to_string()),
+        };
+
+        vec![
+            self.id.clone(),
+            self.name.clone(),
+            revision_id,
+            object_count,
+            format_labels(&self.labels),
+        ]
+    }
+
+    fn id(&self) -> String {
+        self.id.clone()
+    }
+}
+
+impl TableRow for ObjectGroupRevisionView {
+    fn header() -> Vec<&'static str> {
+        vec!["ID", "NAME", "OBJECT GROUP ID", "REVISION", "OBJECTS"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.id.clone(),
+            self.name.clone(),
+            self.object_group_id.clone(),
+            self.revision.to_string(),
+            self.objects.len().to_string(),
+        ]
+    }
+
+    fn id(&self) -> String {
+        self.id.clone()
+    }
+}
+
+impl TableRow for ObjectView {
+    fn header() -> Vec<&'static str> {
+        vec!["ID", "FILENAME", "FILETYPE", "SIZE", "LABELS"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.id.clone(),
+            self.filename.clone(),
+            self.filetype.clone(),
+            self.content_len.to_string(),
+            format_labels(&self.labels),
+        ]
+    }
+
+    fn id(&self) -> String {
+        self.id.clone()
+    }
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum VerifyFindingKind {
+    Missing,
+    Extra,
+    SizeMismatch,
+    ChecksumMismatch,
+}
+
+/// A local file that does not match the remote resource
+#[derive(Serialize, Debug, Clone)]
+pub struct VerifyFindingView {
+    pub path: String,
+    pub kind: VerifyFindingKind,
+    pub expected: Option<String>,
+    pub actual: Option<String>,
+}
+
+impl TableRow for VerifyFindingView {
+    fn header() -> Vec<&'static str> {
+        vec!["PATH", "PROBLEM", "EXPECTED", "ACTUAL"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        let kind = match self.kind {
+            VerifyFindingKind::Missing => "missing",
+            VerifyFindingKind::Extra => "extra",
+            VerifyFindingKind::SizeMismatch => "size mismatch",
+            VerifyFindingKind::ChecksumMismatch => "checksum mismatch",
+        };
+
+        vec![
+            self.path.clone(),
+            kind.to_string(),
+            self.expected.clone().unwrap_or_default(),
+            self.actual.clone().unwrap_or_default(),
+        ]
+    }
+
+    fn id(&self) -> String {
+        self.path.clone()
+    }
+}
+
+/// Metadata of a loaded dataset or dataset version, written into the index directory of the
+/// path style so that the loaded files can be understood without access to the API
+#[derive(Serialize, Debug, Clone)]
+pub struct DatasetIndexView {
+    pub dataset: DatasetView,
+    pub dataset_version: Option<DatasetVersionView>,
+    pub object_groups: Vec<DatasetIndexObjectGroupView>,
+}
+
+/// Metadata of a loaded project, lists all datasets of the project and the index files of the
+/// datasets that objects were loaded from
+#[derive(Serialize, Debug, Clone)]
+pub struct ProjectIndexView {
+    pub project: ProjectView,
+    pub datasets: Vec<ProjectIndexDatasetView>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct ProjectIndexDatasetView {
+    #[serde(flatten)]
+    pub dataset: DatasetView,
+    /// Paths of the index files relative to the base path of the load, empty if no object of
+    /// the dataset was loaded
+    pub index_files: Vec<String>,
+}
+
+/// The revision of an object group the loaded objects belong to
+#[derive(Serialize, Debug, Clone)]
+pub struct DatasetIndexObjectGroupView {
+    pub id: String,
+    pub name: String,
+    pub revision_id: String,
+    pub revision: i64,
+    pub labels: Vec<LabelView>,
+    pub objects: Vec<DatasetIndexObjectView>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct DatasetIndexObjectView {
+    /// Path of the file relative to the base path of the load
+    pub path: String,
+    pub sha256: Option<String>,
+    #[serde(flatten)]
+    pub object: ObjectView,
+}
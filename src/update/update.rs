@@ -1,4 +1,7 @@
-use crate::{client::client, util::cli::UpdateRequest};
+use crate::{
+    client::client,
+    util::{cli::UpdateRequest, error::Result},
+};
 
 use scienceobjectsdb_rust_api::sciobjectsdb::sciobjsdb::api::storage::{
     models::v1::Label as ProtoLabel,
@@ -35,16 +38,16 @@ impl Update {
         return Update { client: client };
     }
 
-    pub async fn update(&mut self, request: UpdateRequest) {
+    pub async fn update(&mut self, request: UpdateRequest) -> Result<()> {
         match request.operation {
             crate::util::cli::UpdateResource::Delete => self.delete_objects(request).await,
             crate::util::cli::UpdateResource::Add => self.add_objects(request).await,
         }
     }
 
-    async fn delete_objects(&mut self, request: UpdateRequest) {
+    async fn delete_objects(&mut self, request: UpdateRequest) -> Result<()> {
         let delete_objects_config: UpdateObjectGroup =
-            self.read_request_file(request.path.clone()).await;
+            self.read_request_file(request.path.clone()).await?;
 
         let objects: Vec<DeleteObjectRequest> = delete_objects_config
             .objects_ids
@@ -82,14 +85,14 @@ impl Update {
         self.client
             .dataset_object_service
             .update_object_group(request)
-            .await
-            .unwrap()
-            .into_inner();
+            .await?;
+
+        Ok(())
     }
 
-    async fn add_objects(&mut self, request: UpdateRequest) {
+    async fn add_objects(&mut self, request: UpdateRequest) -> Result<()> {
         let add_objects_config: UpdateObjectGroup =
-            self.read_request_file(request.path.clone()).await;
+            self.read_request_file(request.path.clone()).await?;
 
         let objects: Vec<AddObjectRequest> = add_objects_config
             .objects_ids
@@ -127,15 +130,15 @@ impl Update {
         self.client
             .dataset_object_service
             .update_object_group(request)
-            .await
-            .unwrap()
-            .into_inner();
+            .await?;
+
+        Ok(())
     }
 
-    pub async fn read_request_file<Z: DeserializeOwned>(&self, file_path: String) -> Z {
-        let data = tokio::fs::read_to_string(file_path).await.unwrap();
-        let create_request: Z = serde_yaml::from_str(data.as_str()).unwrap();
+    pub async fn read_request_file<Z: DeserializeOwned>(&self, file_path: String) -> Result<Z> {
+        let data = tokio::fs::read_to_string(file_path).await?;
+        let create_request: Z = serde_yaml::from_str(data.as_str())?;
 
-        return create_request;
+        return Ok(create_request);
     }
 }
@@ -0,0 +1,57 @@
+use std::path::Path;
+
+use scienceobjectsdb_rust_api::sciobjectsdb::sciobjsdb::api::storage::models::v1::Label;
+use sha2::{Digest, Sha256};
+use tokio::io::AsyncReadExt;
+
+use super::error::Result;
+
+/// Key of the object label that stores the hex encoded SHA-256 checksum of the object data
+pub const SHA256_LABEL_KEY: &str = "sha256";
+
+const CHECKSUM_READ_BUFFER_SIZE: usize = 1024 * 1024;
+
+/// A checksum that did not match the expected value
+#[derive(Debug, Clone)]
+pub struct ChecksumMismatch {
+    pub path: String,
+    pub expected: String,
+    pub actual: String,
+}
+
+pub async fn sha256_file(path: &Path) -> Result<String> {
+    let mut hasher = Sha256::new();
+    update_from_file(&mut hasher, path).await?;
+
+    return Ok(hex::encode(hasher.finalize()));
+}
+
+/// Feeds the content of the file into the hasher
+pub async fn update_from_file(hasher: &mut Sha256, path: &Path) -> Result<()> {
+    let mut file = tokio::fs::File::open(path).await?;
+    let mut buffer = vec![0u8; CHECKSUM_READ_BUFFER_SIZE];
+
+    loop {
+        let read = file.read(&mut buffer).await?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+
+    Ok(())
+}
+
+/// Returns the SHA-256 checksum stored in the labels of an object, if there is one
+pub fn sha256_from_labels(labels: &[Label]) -> Option<String> {
+    labels
+        .iter()
+        .find(|label| label.key == SHA256_LABEL_KEY)
+        .map(|label| label.value.to_lowercase())
+}
+
+/// S3 returns the MD5 of the uploaded data as quoted ETag for unencrypted single part uploads
+/// and parts of multipart uploads
+pub fn etag_matches_md5(etag: &str, md5: &str) -> bool {
+    etag.trim_matches('"').eq_ignore_ascii_case(md5)
+}
@@ -1,12 +1,38 @@
 use clap::{ArgEnum, Parser};
+use scienceobjectsdb_rust_api::sciobjectsdb::sciobjsdb::api::storage::models::v1::{
+    Label, LabelFilter,
+};
+
+use super::{config::TransferDefaults, error::CliError};
+
+const DEFAULT_LOAD_WORKERS: usize = 10;
+const DEFAULT_LOAD_QUEUE_SIZE: usize = 500;
+const DEFAULT_CREATE_WORKERS: usize = 4;
+const DEFAULT_PARALLEL_PARTS: usize = 4;
+const DEFAULT_PART_SIZE: usize = 5 * 1024 * 1024;
+const DEFAULT_MULTIPART_THRESHOLD: usize = 5 * 1024 * 1024;
 
 #[derive(Parser)]
 pub struct Ls {
     /// The targeted resource
     #[clap(arg_enum)]
     pub resource: LsResource,
-    /// The targeted resource parent id
-    pub id: String,
+    /// The targeted resource parent id, defaults to the project of the profile for
+    /// project-datasets
+    pub id: Option<String>,
+    /// Number of entries requested per page, only used by paginated resources
+    #[clap(long, default_value = "500", parse(try_from_str = parse_page_size))]
+    pub page_size: u64,
+    /// Only list entries after the entry with the given id
+    #[clap(long)]
+    pub after: Option<String>,
+    /// Request all pages instead of only the first one. Table and id output is printed as
+    /// soon as each page arrives
+    #[clap(long)]
+    pub all: bool,
+    /// Only list entries with the given label, can be repeated
+    #[clap(long = "label", parse(try_from_str = parse_label_selector))]
+    pub labels: Vec<LabelSelector>,
 }
 
 #[derive(Parser)]
@@ -14,9 +40,10 @@ pub struct Describe {
     /// The resource type to describe
     #[clap(arg_enum, short = 'r')]
     pub resource: Resource,
+    /// The id of the resource to describe, defaults to the project of the profile for
+    /// projects
     #[clap(short = 'i')]
-    /// The id of the resource to describe
-    pub id: String,
+    pub id: Option<String>,
 }
 
 #[derive(Parser)]
@@ -41,15 +68,127 @@ pub struct Load {
     /// The resource type to load
     #[clap(arg_enum, short = 'r')]
     pub resource: Resource,
-    /// The id of the resource to load
+    /// The id of the resource to load, defaults to the project of the profile for projects
     #[clap(short = 'i')]
-    pub id: String,
+    pub id: Option<String>,
     /// The base path of the loaded resource
+    #[clap(short = 'p', required_unless_present = "archive")]
+    pub path: Option<String>,
+    /// Download target path style
+    #[clap(arg_enum, short = 's', default_value = "canonical")]
+    pub path_style: DownloadPathStyle,
+    /// Pattern of the file paths for the template path style, e.g.
+    /// {project_id}/{dataset_name}/{object_group_name}/{label:sample}/{filename}.{filetype}.
+    /// Available placeholders: project_id, dataset_id, dataset_name, dataset_version_id,
+    /// object_group_name, object_id, filename, filetype and label:<key>. Placeholders without
+    /// a value are left out together with a dot in front of them, a directory that would be
+    /// empty is named _
+    #[clap(long)]
+    pub path_template: Option<String>,
+    /// Skip files that are already complete on disk and continue partially downloaded files
+    #[clap(long)]
+    pub resume: bool,
+    /// What to do if several objects would be stored at the same path
+    #[clap(arg_enum, long, default_value = "fail")]
+    pub on_collision: CollisionPolicy,
+    /// Format of the index files with the metadata of the loaded datasets, they are written
+    /// into the `_index` directory of the path style
+    #[clap(arg_enum, long, default_value = "json")]
+    pub index_format: IndexFormat,
+    /// Write the objects into a .tar, .tar.gz or .zip archive instead of a directory. The
+    /// objects are downloaded one after another and streamed into the archive, a failed
+    /// download or checksum mismatch removes the archive
+    #[clap(long, conflicts_with_all = &["path", "resume"])]
+    pub archive: Option<String>,
+    /// Only load object groups with the given label, can be repeated
+    #[clap(long = "label", parse(try_from_str = parse_label_selector))]
+    pub labels: Vec<LabelSelector>,
+    /// Number of objects that are downloaded at the same time, also limits the number of
+    /// datasets of a project that are listed at the same time. The pages of a single dataset
+    /// are always requested one after another. Defaults to 10
+    #[clap(long)]
+    pub workers: Option<usize>,
+    /// Number of objects that are queued for download ahead of the workers. Defaults to 500
+    #[clap(long)]
+    pub queue_size: Option<usize>,
+}
+
+impl Load {
+    /// Options that were not given on the command line are taken from the profile
+    pub fn apply_profile(&mut self, transfer: &TransferDefaults) {
+        self.workers = self.workers.or(transfer.workers);
+        self.queue_size = self.queue_size.or(transfer.queue_size);
+    }
+
+    pub fn workers(&self) -> usize {
+        return std::cmp::max(self.workers.unwrap_or(DEFAULT_LOAD_WORKERS), 1);
+    }
+
+    pub fn queue_size(&self) -> usize {
+        return std::cmp::max(self.queue_size.unwrap_or(DEFAULT_LOAD_QUEUE_SIZE), 1);
+    }
+
+    /// The id of the loaded project, if a whole project is loaded
+    pub fn project_id(&self) -> Option<String> {
+        match self.resource {
+            Resource::Project => self.id.clone(),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Parser)]
+pub struct Cat {
+    /// The id of the object to print
+    #[clap(short = 'i')]
+    pub id: String,
+    /// Only print the given byte range, e.g. 0-1023, 1024- or -512 for the last 512 bytes
+    #[clap(long, parse(try_from_str = parse_byte_range))]
+    pub range: Option<ByteRange>,
+}
+
+#[derive(Parser)]
+pub struct Verify {
+    /// The resource type to verify
+    #[clap(arg_enum, short = 'r')]
+    pub resource: Resource,
+    /// The id of the resource to verify, defaults to the project of the profile for projects
+    #[clap(short = 'i')]
+    pub id: Option<String>,
+    /// The base path the resource was loaded to
     #[clap(short = 'p')]
     pub path: String,
-    /// Download target path style
+    /// Path style the resource was loaded with
     #[clap(arg_enum, short = 's', default_value = "canonical")]
     pub path_style: DownloadPathStyle,
+    /// Pattern of the file paths for the template path style, see load
+    #[clap(long)]
+    pub path_template: Option<String>,
+    /// Collision policy the resource was loaded with
+    #[clap(arg_enum, long, default_value = "fail")]
+    pub on_collision: CollisionPolicy,
+    /// Only verify object groups with the given label, can be repeated
+    #[clap(long = "label", parse(try_from_str = parse_label_selector))]
+    pub labels: Vec<LabelSelector>,
+    /// Only compare file sizes and skip the checksum verification
+    #[clap(long)]
+    pub size_only: bool,
+    /// Number of files that are verified at the same time, also limits the number of
+    /// datasets of a project that are listed at the same time. The pages of a single dataset
+    /// are always requested one after another. Defaults to 10
+    #[clap(long)]
+    pub workers: Option<usize>,
+}
+
+impl Verify {
+    /// Options that were not given on the command line are taken from the profile
+    pub fn apply_profile(&mut self, transfer: &TransferDefaults) {
+        self.workers = self.workers.or(transfer.workers);
+    }
+
+    pub fn workers(&self) -> usize {
+        return std::cmp::max(self.workers.unwrap_or(DEFAULT_LOAD_WORKERS), 1);
+    }
 }
 
 #[derive(Parser, Debug)]
@@ -61,6 +200,55 @@ pub struct CreateRequest {
     /// under examples
     #[clap(short = 'p')]
     pub path: String,
+    /// Continue multipart uploads that were interrupted in a previous run instead of starting
+    /// over. Files that changed since the interrupted run are uploaded again
+    #[clap(long)]
+    pub resume: bool,
+    /// Number of parts of a multipart upload that are uploaded at the same time. Defaults to 4
+    #[clap(long)]
+    pub parallel_parts: Option<usize>,
+    /// Number of objects that are uploaded at the same time. Defaults to 4
+    #[clap(long)]
+    pub workers: Option<usize>,
+    /// Files of at least this size are uploaded in multiple parts, e.g. 100M. Defaults to 5MiB
+    #[clap(long, parse(try_from_str = parse_byte_size))]
+    pub multipart_threshold: Option<usize>,
+    /// Size of the parts of multipart uploads, at least 5MiB. The part size is increased
+    /// automatically if a file would need more than 10000 parts. Defaults to 5MiB
+    #[clap(long, parse(try_from_str = parse_byte_size))]
+    pub part_size: Option<usize>,
+    /// Compare the MD5 of the uploaded data with the ETag returned by the storage. Only
+    /// works with storages that use the MD5 as ETag, e.g. S3 without server side encryption
+    #[clap(long)]
+    pub verify_etag: bool,
+}
+
+impl CreateRequest {
+    /// Options that were not given on the command line are taken from the profile
+    pub fn apply_profile(&mut self, transfer: &TransferDefaults) {
+        self.parallel_parts = self.parallel_parts.or(transfer.parallel_parts);
+        self.workers = self.workers.or(transfer.workers);
+        self.multipart_threshold = self.multipart_threshold.or(transfer.multipart_threshold);
+        self.part_size = self.part_size.or(transfer.part_size);
+    }
+
+    pub fn parallel_parts(&self) -> usize {
+        return std::cmp::max(self.parallel_parts.unwrap_or(DEFAULT_PARALLEL_PARTS), 1);
+    }
+
+    pub fn workers(&self) -> usize {
+        return std::cmp::max(self.workers.unwrap_or(DEFAULT_CREATE_WORKERS), 1);
+    }
+
+    pub fn multipart_threshold(&self) -> usize {
+        return self
+            .multipart_threshold
+            .unwrap_or(DEFAULT_MULTIPART_THRESHOLD);
+    }
+
+    pub fn part_size(&self) -> usize {
+        return self.part_size.unwrap_or(DEFAULT_PART_SIZE);
+    }
 }
 
 #[derive(Parser)]
@@ -86,6 +274,7 @@ pub enum Resource {
     Dataset,
     DatasetVersion,
     ObjectGroup,
+    Object,
 }
 
 #[derive(PartialEq, Debug, ArgEnum, Clone)]
@@ -106,8 +295,171 @@ pub enum LsResource {
     DatasetObjects,
 }
 
+/// Handling of objects that would be stored at the same path as another object. With suffix
+/// and skip all objects are listed before the first one is loaded, the object with the
+/// smallest id keeps the path
+#[derive(PartialEq, Debug, ArgEnum, Clone, Copy)]
+pub enum CollisionPolicy {
+    /// Only the first object is loaded and the command fails after all other objects
+    Fail,
+    /// The id of the object is added to the file name of the other objects
+    Suffix,
+    /// Only the object with the smallest id is loaded
+    Skip,
+}
+
+#[derive(PartialEq, Debug, ArgEnum, Clone, Copy)]
+pub enum IndexFormat {
+    Json,
+    Yaml,
+    /// No index files are written
+    None,
+}
+
 #[derive(PartialEq, Debug, ArgEnum, Clone)]
 pub enum DownloadPathStyle {
     Canonical,
     Flat,
+    Template,
+}
+
+#[derive(PartialEq, Debug, ArgEnum, Clone, Copy)]
+pub enum OutputFormat {
+    Json,
+    Yaml,
+    Table,
+    Ids,
+}
+
+/// A label given as `key=value` on the command line
+#[derive(PartialEq, Debug, Clone)]
+pub struct LabelSelector {
+    pub key: String,
+    pub value: String,
+}
+
+impl LabelSelector {
+    pub fn to_proto_label(&self) -> Label {
+        return Label {
+            key: self.key.clone(),
+            value: self.value.clone(),
+        };
+    }
+
+    pub fn matches(&self, labels: &[Label]) -> bool {
+        return labels
+            .iter()
+            .any(|label| label.key == self.key && label.value == self.value);
+    }
+}
+
+fn parse_label_selector(label: &str) -> Result<LabelSelector, String> {
+    match label.split_once('=') {
+        Some((key, value)) if !key.is_empty() => Ok(LabelSelector {
+            key: key.to_string(),
+            value: value.to_string(),
+        }),
+        _ => Err(format!("invalid label {:?}, expected key=value", label)),
+    }
+}
+
+/// Page sizes of at least 1, empty pages would never reach the end of a listing
+pub fn parse_page_size(page_size: &str) -> Result<u64, String> {
+    match page_size.trim().parse::<u64>() {
+        Ok(page_size) if page_size >= 1 => Ok(page_size),
+        _ => Err(format!(
+            "invalid page size {:?}, expected a number of at least 1",
+            page_size
+        )),
+    }
+}
+
+/// Parses sizes like `512`, `64K`, `5MiB` or `1G`. Units are binary, so `1K` are 1024 bytes
+pub fn parse_byte_size(size: &str) -> Result<usize, String> {
+    let size = size.trim();
+    let unit_start = size
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(size.len());
+    let (value, unit) = size.split_at(unit_start);
+
+    let value: usize = value
+        .parse()
+        .map_err(|_| format!("invalid size {:?}", size))?;
+    let multiplier: usize = match unit.trim().to_uppercase().as_str() {
+        "" | "B" => 1,
+        "K" | "KB" | "KIB" => 1 << 10,
+        "M" | "MB" | "MIB" => 1 << 20,
+        "G" | "GB" | "GIB" => 1 << 30,
+        "T" | "TB" | "TIB" => 1 << 40,
+        _ => return Err(format!("invalid size unit {:?}", unit)),
+    };
+
+    return value
+        .checked_mul(multiplier)
+        .ok_or_else(|| format!("size {:?} is too large", size));
+}
+
+/// An inclusive HTTP byte range, a missing start selects the last `end` bytes
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ByteRange {
+    pub start: Option<u64>,
+    pub end: Option<u64>,
+}
+
+impl ByteRange {
+    pub fn header_value(&self) -> String {
+        let format_bound = |bound: Option<u64>| bound.map(|x| x.to_string()).unwrap_or_default();
+        format!(
+            "bytes={}-{}",
+            format_bound(self.start),
+            format_bound(self.end)
+        )
+    }
+}
+
+pub fn parse_byte_range(range: &str) -> Result<ByteRange, String> {
+    let invalid_range = || format!("invalid range {:?}, expected start-end", range);
+    let (start, end) = range.trim().split_once('-').ok_or_else(invalid_range)?;
+
+    let parse_bound = |bound: &str| match bound {
+        "" => Ok(None),
+        bound => bound.parse::<u64>().map(Some).map_err(|_| invalid_range()),
+    };
+    let byte_range = ByteRange {
+        start: parse_bound(start)?,
+        end: parse_bound(end)?,
+    };
+
+    match (byte_range.start, byte_range.end) {
+        (None, None) => Err(invalid_range()),
+        (Some(start), Some(end)) if start > end => Err(invalid_range()),
+        _ => Ok(byte_range),
+    }
+}
+
+/// The id of load, verify or describe, only projects can default to the profile project_id
+pub fn required_id(id: &Option<String>) -> super::error::Result<String> {
+    return id.clone().ok_or_else(|| {
+        CliError::InvalidInput(
+            "the id of the resource is required, -i can only be omitted for projects if the \
+             profile has a project_id"
+                .to_string(),
+        )
+    });
+}
+
+/// Builds the server side label filter, no filter is applied if no labels are given
+pub fn label_filter(labels: &[LabelSelector]) -> Option<LabelFilter> {
+    if labels.is_empty() {
+        return None;
+    }
+
+    return Some(LabelFilter {
+        labels: labels.iter().map(|x| x.to_proto_label()).collect(),
+    });
+}
+
+/// Checks that all given labels are set, used where the server can not filter by them
+pub fn matches_labels(selectors: &[LabelSelector], labels: &[Label]) -> bool {
+    return selectors.iter().all(|x| x.matches(labels));
 }
@@ -1,20 +1,146 @@
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 use serde_yaml;
 use tokio::io::AsyncReadExt;
 
-use std::path::{Path, PathBuf};
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+use super::{
+    cli::parse_byte_size,
+    error::{CliError, Result},
+};
+
+/// The settings of the selected profile, missing values are taken from the top level of the
+/// config file
+#[derive(Debug, Clone)]
 pub struct Config {
     pub api_key: String,
     pub endpoint: String,
+    pub retry: RetryConfig,
+    /// Project used by commands if no project id is given
+    pub project_id: Option<String>,
+    pub transfer: TransferDefaults,
+}
+
+/// Settings given on the command line or as environment variables, they take precedence over
+/// the config file
+#[derive(Debug, Clone, Default)]
+pub struct ConfigOverrides {
+    pub endpoint: Option<String>,
+    pub api_key: Option<String>,
+}
+
+/// Layout of the config file. The top level settings apply to all profiles
+#[derive(Deserialize, Debug, Default)]
+struct ConfigFile {
+    #[serde(flatten)]
+    defaults: Profile,
+    default_profile: Option<String>,
+    #[serde(default)]
+    profiles: BTreeMap<String, Profile>,
+    /// Top level keys that are neither settings nor profiles, serde does not support
+    /// deny_unknown_fields together with flatten
+    #[serde(flatten)]
+    unknown: BTreeMap<String, serde_yaml::Value>,
+}
+
+/// Settings of an instance, e.g. staging or production
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct Profile {
+    pub api_key: Option<String>,
+    pub endpoint: Option<String>,
+    pub retry: Option<RetryConfig>,
+    pub project_id: Option<String>,
+    pub transfer: TransferDefaults,
+}
+
+/// Defaults of the transfer options of load, verify and create, options given on the command
+/// line take precedence. Sizes are given in bytes or with a unit like 50M
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct TransferDefaults {
+    #[serde(deserialize_with = "deserialize_byte_size")]
+    pub limit_rate: Option<usize>,
+    pub workers: Option<usize>,
+    pub queue_size: Option<usize>,
+    pub parallel_parts: Option<usize>,
+    #[serde(deserialize_with = "deserialize_byte_size")]
+    pub part_size: Option<usize>,
+    #[serde(deserialize_with = "deserialize_byte_size")]
+    pub multipart_threshold: Option<usize>,
+}
+
+impl TransferDefaults {
+    /// Values of self take precedence over the values of other
+    fn or(self, other: TransferDefaults) -> Self {
+        return TransferDefaults {
+            limit_rate: self.limit_rate.or(other.limit_rate),
+            workers: self.workers.or(other.workers),
+            queue_size: self.queue_size.or(other.queue_size),
+            parallel_parts: self.parallel_parts.or(other.parallel_parts),
+            part_size: self.part_size.or(other.part_size),
+            multipart_threshold: self.multipart_threshold.or(other.multipart_threshold),
+        };
+    }
+}
+
+fn deserialize_byte_size<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> std::result::Result<Option<usize>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum ByteSize {
+        Bytes(usize),
+        Text(String),
+    }
+
+    match Option::<ByteSize>::deserialize(deserializer)? {
+        Some(ByteSize::Bytes(bytes)) => Ok(Some(bytes)),
+        Some(ByteSize::Text(text)) => parse_byte_size(&text)
+            .map(Some)
+            .map_err(serde::de::Error::custom),
+        None => Ok(None),
+    }
+}
+
+/// Retry behaviour for transient failures of API calls and data transfers
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct RetryConfig {
+    /// Maximum number of attempts including the first one
+    pub max_attempts: u32,
+    pub initial_backoff_ms: u64,
+    pub max_backoff_ms: u64,
+    pub multiplier: f64,
+    /// gRPC status codes that are retried, e.g. unavailable or deadline_exceeded
+    pub retryable_status: Vec<String>,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        RetryConfig {
+            max_attempts: 5,
+            initial_backoff_ms: 200,
+            max_backoff_ms: 10000,
+            multiplier: 2.0,
+            retryable_status: vec!["unavailable".to_string(), "deadline_exceeded".to_string()],
+        }
+    }
 }
 
 const DEFAULT_CONF_PATH: [&str; 2] = [".sciobjsdb/config.yaml", ".config/sciobjsdb/config.yaml"];
+pub const CONFIG_ENV_VAR: &str = "SCIOBJSDB_CONFIG";
+pub const PROFILE_ENV_VAR: &str = "SCIOBJSDB_PROFILE";
+pub const ENDPOINT_ENV_VAR: &str = "SCIOBJSDB_ENDPOINT";
+pub const API_KEY_ENV_VAR: &str = "SCIOBJSDB_API_KEY";
 
 impl Config {
-    pub async fn new() -> Self {
-        let dirs = directories::UserDirs::new().unwrap();
+    pub async fn new(profile: Option<&str>, overrides: ConfigOverrides) -> Result<Self> {
+        let dirs = directories::UserDirs::new()
+            .ok_or_else(|| CliError::Config("could not determine home directory".to_string()))?;
         for conf_path in DEFAULT_CONF_PATH {
             let search_path = Path::new(conf_path);
             let homedir = dirs.home_dir();
@@ -23,28 +149,127 @@ impl Config {
             path_buf.push(search_path);
             let path = path_buf.as_path();
             if path.is_file() {
-                let mut data = String::new();
-                let mut conf_file = tokio::fs::File::open(path).await.unwrap();
-                conf_file.read_to_string(&mut data).await.unwrap();
-                let conf: Config = serde_yaml::from_str(data.as_str()).unwrap();
-
-                return conf;
+                return Config::read_config(path, profile, overrides).await;
             }
         }
 
-        panic!("could not find default config")
+        // profiles only exist in a config file, so a profile is not silently ignored
+        if let Some(profile) = profile {
+            return Err(CliError::Config(format!(
+                "profile {:?} requires a config file, but none was found in the home directory \
+                 (searched for {}). Use -c to specify the config file, or unset --profile and {} \
+                 to run with --endpoint and --api-key only",
+                profile,
+                DEFAULT_CONF_PATH.join(" and "),
+                PROFILE_ENV_VAR
+            )));
+        }
+
+        // without a config file all settings have to be given as options or environment variables
+        if overrides.endpoint.is_some() && overrides.api_key.is_some() {
+            return Config::select_profile(ConfigFile::default(), None, overrides, Path::new(""));
+        }
+
+        Err(CliError::Config(format!(
+            "could not find default config, searched for {} in the home directory. Without a \
+             config file the endpoint and api key have to be set with --endpoint and --api-key \
+             or {} and {}",
+            DEFAULT_CONF_PATH.join(" and "),
+            ENDPOINT_ENV_VAR,
+            API_KEY_ENV_VAR
+        )))
     }
-    pub async fn specified_path(config_path: &str) -> Self {
+    pub async fn specified_path(
+        config_path: &str,
+        profile: Option<&str>,
+        overrides: ConfigOverrides,
+    ) -> Result<Self> {
         let config_path = Path::new(config_path);
         if config_path.is_file() {
-            let mut data = String::new();
-            let mut conf_file = tokio::fs::File::open(config_path).await.unwrap();
-            conf_file.read_to_string(&mut data).await.unwrap();
-            let conf: Config = serde_yaml::from_str(data.as_str()).unwrap();
+            return Config::read_config(config_path, profile, overrides).await;
+        }
+
+        Err(CliError::Config(format!(
+            "could not find specified config {:?}",
+            config_path
+        )))
+    }
 
-            return conf;
+    async fn read_config(
+        path: &Path,
+        profile: Option<&str>,
+        overrides: ConfigOverrides,
+    ) -> Result<Self> {
+        let mut data = String::new();
+        let mut conf_file = tokio::fs::File::open(path).await?;
+        conf_file.read_to_string(&mut data).await?;
+        let conf: ConfigFile = serde_yaml::from_str(data.as_str())
+            .map_err(|err| CliError::Config(format!("could not parse {:?}: {}", path, err)))?;
+        if !conf.unknown.is_empty() {
+            return Err(CliError::Config(format!(
+                "unknown keys in {:?}: {}",
+                path,
+                conf.unknown
+                    .keys()
+                    .cloned()
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            )));
         }
 
-        panic!("could not find specified config {:?}", config_path)
+        return Config::select_profile(conf, profile, overrides, path);
+    }
+
+    /// Merges the overrides, the selected profile and the top level settings in this order of
+    /// precedence. The profile given on the command line takes precedence over the default
+    /// profile of the file
+    fn select_profile(
+        mut conf: ConfigFile,
+        profile: Option<&str>,
+        overrides: ConfigOverrides,
+        path: &Path,
+    ) -> Result<Self> {
+        let profile_name = profile
+            .map(|x| x.to_string())
+            .or_else(|| conf.default_profile.clone());
+        let profile = match &profile_name {
+            Some(profile_name) => conf.profiles.remove(profile_name).ok_or_else(|| {
+                CliError::Config(format!(
+                    "profile {:?} not found in {:?}, available profiles: {}",
+                    profile_name,
+                    path,
+                    conf.profiles
+                        .keys()
+                        .cloned()
+                        .collect::<Vec<String>>()
+                        .join(", ")
+                ))
+            })?,
+            None => Profile::default(),
+        };
+        let defaults = conf.defaults;
+
+        let missing = |key: &str| {
+            CliError::Config(match &profile_name {
+                Some(profile_name) => format!("{} is not set for profile {:?}", key, profile_name),
+                None => format!("{} is not set in {:?}", key, path),
+            })
+        };
+
+        return Ok(Config {
+            api_key: overrides
+                .api_key
+                .or(profile.api_key)
+                .or(defaults.api_key)
+                .ok_or_else(|| missing("api_key"))?,
+            endpoint: overrides
+                .endpoint
+                .or(profile.endpoint)
+                .or(defaults.endpoint)
+                .ok_or_else(|| missing("endpoint"))?,
+            retry: profile.retry.or(defaults.retry).unwrap_or_default(),
+            project_id: profile.project_id.or(defaults.project_id),
+            transfer: profile.transfer.or(defaults.transfer),
+        });
     }
 }
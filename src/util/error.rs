@@ -0,0 +1,185 @@
+use std::fmt;
+
+use tonic::Code;
+
+use super::checksum::ChecksumMismatch;
+
+pub type Result<T> = std::result::Result<T, CliError>;
+
+/// Exit codes of the cli, so that scripts can react to the type of the failure
+pub const EXIT_GENERAL: i32 = 1;
+pub const EXIT_INVALID_INPUT: i32 = 2;
+pub const EXIT_NOT_FOUND: i32 = 3;
+pub const EXIT_PERMISSION_DENIED: i32 = 4;
+pub const EXIT_UNAUTHENTICATED: i32 = 5;
+pub const EXIT_UNAVAILABLE: i32 = 6;
+pub const EXIT_IO: i32 = 7;
+pub const EXIT_CONFIG: i32 = 8;
+pub const EXIT_TRANSFER: i32 = 9;
+pub const EXIT_CHECKSUM_MISMATCH: i32 = 10;
+pub const EXIT_VERIFICATION_FAILED: i32 = 11;
+pub const EXIT_PATH_COLLISION: i32 = 12;
+pub const EXIT_SKIPPED_OBJECT_GROUPS: i32 = 13;
+
+#[derive(Debug)]
+pub enum CliError {
+    /// An error returned by the gRPC API
+    Status(Box<tonic::Status>),
+    /// The connection to the API endpoint failed
+    Transport(tonic::transport::Error),
+    /// An upload to or download from a presigned link failed
+    Http(reqwest::Error),
+    /// A download from a presigned link did not return the size of the object
+    IncompleteTransfer(String),
+    Io(std::io::Error),
+    Yaml(serde_yaml::Error),
+    Json(serde_json::Error),
+    UploadState(sled::Error),
+    Config(String),
+    InvalidInput(String),
+    InvalidResponse(String),
+    /// Transferred data did not match the expected checksums
+    ChecksumMismatch(Vec<ChecksumMismatch>),
+    /// A local directory tree does not match the remote resource
+    VerificationFailed(String),
+    /// Several objects of a download would be stored at the same path
+    PathCollision(String),
+    /// Object groups without a current revision or without the requested labels were not
+    /// loaded
+    SkippedObjectGroups(u64),
+    Internal(String),
+}
+
+impl CliError {
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            CliError::Status(status) => match status.code() {
+                Code::NotFound => EXIT_NOT_FOUND,
+                Code::PermissionDenied => EXIT_PERMISSION_DENIED,
+                Code::Unauthenticated => EXIT_UNAUTHENTICATED,
+                Code::Unavailable => EXIT_UNAVAILABLE,
+                Code::InvalidArgument => EXIT_INVALID_INPUT,
+                _ => EXIT_GENERAL,
+            },
+            CliError::Transport(_) => EXIT_UNAVAILABLE,
+            CliError::Http(_) | CliError::IncompleteTransfer(_) => EXIT_TRANSFER,
+            CliError::Io(_) | CliError::UploadState(_) => EXIT_IO,
+            CliError::Yaml(_) | CliError::Json(_) | CliError::InvalidInput(_) => EXIT_INVALID_INPUT,
+            CliError::Config(_) => EXIT_CONFIG,
+            CliError::ChecksumMismatch(_) => EXIT_CHECKSUM_MISMATCH,
+            CliError::VerificationFailed(_) => EXIT_VERIFICATION_FAILED,
+            CliError::PathCollision(_) => EXIT_PATH_COLLISION,
+            CliError::SkippedObjectGroups(_) => EXIT_SKIPPED_OBJECT_GROUPS,
+            CliError::InvalidResponse(_) | CliError::Internal(_) => EXIT_GENERAL,
+        }
+    }
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CliError::Status(status) => match status.code() {
+                Code::NotFound => write!(f, "resource not found: {}", status.message()),
+                Code::PermissionDenied => write!(f, "permission denied: {}", status.message()),
+                Code::Unauthenticated => write!(
+                    f,
+                    "authentication failed, please check the configured api_key: {}",
+                    status.message()
+                ),
+                Code::Unavailable => write!(
+                    f,
+                    "the API is currently unavailable, please check the configured endpoint: {}",
+                    status.message()
+                ),
+                Code::InvalidArgument => write!(f, "invalid request: {}", status.message()),
+                code => write!(f, "request failed ({:?}): {}", code, status.message()),
+            },
+            CliError::Transport(err) => write!(f, "could not connect to the API: {}", err),
+            CliError::Http(err) => write!(f, "data transfer failed: {}", err),
+            CliError::IncompleteTransfer(msg) => write!(f, "data transfer failed: {}", msg),
+            CliError::Io(err) => write!(f, "{}", err),
+            CliError::Yaml(err) => write!(f, "could not parse yaml: {}", err),
+            CliError::Json(err) => write!(f, "could not serialize json: {}", err),
+            CliError::UploadState(err) => write!(f, "could not access upload state: {}", err),
+            CliError::Config(msg) => write!(f, "invalid configuration: {}", msg),
+            CliError::InvalidInput(msg) => write!(f, "{}", msg),
+            CliError::InvalidResponse(msg) => {
+                write!(f, "unexpected response from the API: {}", msg)
+            }
+            CliError::ChecksumMismatch(mismatches) => {
+                write!(
+                    f,
+                    "checksum verification failed for {} file(s):",
+                    mismatches.len()
+                )?;
+                for mismatch in mismatches {
+                    write!(
+                        f,
+                        "\n  {}: expected {}, got {}",
+                        mismatch.path, mismatch.expected, mismatch.actual
+                    )?;
+                }
+                Ok(())
+            }
+            CliError::VerificationFailed(msg) => write!(f, "verification failed: {}", msg),
+            CliError::PathCollision(msg) => write!(f, "path collision: {}", msg),
+            CliError::SkippedObjectGroups(count) => write!(
+                f,
+                "{} object group(s) were skipped because they have no current revision or not \
+                 the given labels",
+                count
+            ),
+            CliError::Internal(msg) => write!(f, "{}", msg),
+        }
+    }
+}
+
+impl std::error::Error for CliError {}
+
+impl From<tonic::Status> for CliError {
+    fn from(status: tonic::Status) -> Self {
+        CliError::Status(Box::new(status))
+    }
+}
+
+impl From<tonic::transport::Error> for CliError {
+    fn from(err: tonic::transport::Error) -> Self {
+        CliError::Transport(err)
+    }
+}
+
+impl From<reqwest::Error> for CliError {
+    fn from(err: reqwest::Error) -> Self {
+        CliError::Http(err)
+    }
+}
+
+impl From<std::io::Error> for CliError {
+    fn from(err: std::io::Error) -> Self {
+        CliError::Io(err)
+    }
+}
+
+impl From<serde_yaml::Error> for CliError {
+    fn from(err: serde_yaml::Error) -> Self {
+        CliError::Yaml(err)
+    }
+}
+
+impl From<serde_json::Error> for CliError {
+    fn from(err: serde_json::Error) -> Self {
+        CliError::Json(err)
+    }
+}
+
+impl From<sled::Error> for CliError {
+    fn from(err: sled::Error) -> Self {
+        CliError::UploadState(err)
+    }
+}
+
+impl<T> From<async_channel::SendError<T>> for CliError {
+    fn from(_: async_channel::SendError<T>) -> Self {
+        CliError::Internal("transfer queue was closed unexpectedly".to_string())
+    }
+}
@@ -1,2 +1,5 @@
+pub mod checksum;
 pub mod cli;
 pub mod config;
+pub mod error;
+pub mod progress;
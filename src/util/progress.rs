@@ -0,0 +1,269 @@
+use std::{
+    io::IsTerminal,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Weak,
+    },
+    time::{Duration, Instant},
+};
+
+use indicatif::{HumanBytes, HumanDuration, MultiProgress, ProgressBar, ProgressStyle};
+
+const PROGRESS_LOG_INTERVAL: Duration = Duration::from_secs(10);
+const TOTAL_PROGRESS_TEMPLATE: &str =
+    "{prefix} {msg} files [{bar:30}] {bytes}/{total_bytes} {binary_bytes_per_sec} ETA {eta}";
+const FILE_PROGRESS_TEMPLATE: &str = "  {msg:40!} [{bar:30}] {bytes}/{total_bytes}";
+
+/// Aggregate progress of the files of an upload or download. Progress bars are drawn on stderr
+/// if it is a terminal, otherwise the progress is logged periodically
+#[derive(Clone)]
+pub struct TransferProgress {
+    state: Arc<ProgressState>,
+}
+
+struct ProgressState {
+    action: &'static str,
+    started: Instant,
+    total_bytes: AtomicU64,
+    done_bytes: AtomicU64,
+    total_files: AtomicU64,
+    done_files: AtomicU64,
+    skipped_files: AtomicU64,
+    failed_files: AtomicU64,
+    bars: Option<ProgressBars>,
+}
+
+struct ProgressBars {
+    multi: MultiProgress,
+    total: ProgressBar,
+}
+
+/// Progress of a single file, the transferred bytes are added to the aggregate progress
+#[derive(Clone)]
+pub struct FileProgress {
+    state: Arc<ProgressState>,
+    position: Arc<AtomicU64>,
+    bar: Option<ProgressBar>,
+}
+
+impl TransferProgress {
+    /// Creates the progress for a transfer, `action` is used in the log lines and the summary,
+    /// e.g. "Downloaded"
+    pub fn new(action: &'static str) -> Self {
+        let bars = match std::io::stderr().is_terminal() {
+            true => {
+                let multi = MultiProgress::new();
+                let total = multi.add(ProgressBar::new(0));
+                total.set_style(progress_style(TOTAL_PROGRESS_TEMPLATE));
+                total.set_prefix(action);
+                Some(ProgressBars {
+                    multi: multi,
+                    total: total,
+                })
+            }
+            false => None,
+        };
+
+        let state = Arc::new(ProgressState {
+            action: action,
+            started: Instant::now(),
+            total_bytes: AtomicU64::new(0),
+            done_bytes: AtomicU64::new(0),
+            total_files: AtomicU64::new(0),
+            done_files: AtomicU64::new(0),
+            skipped_files: AtomicU64::new(0),
+            failed_files: AtomicU64::new(0),
+            bars: bars,
+        });
+
+        if state.bars.is_none() {
+            tokio::spawn(log_progress(Arc::downgrade(&state)));
+        }
+
+        return TransferProgress { state: state };
+    }
+
+    /// Adds a file to the total and returns its progress
+    pub fn start_file(&self, name: String, len: u64) -> FileProgress {
+        self.add_file(len);
+
+        let bar = self.state.bars.as_ref().map(|bars| {
+            let bar = bars.multi.add(ProgressBar::new(len));
+            bar.set_style(progress_style(FILE_PROGRESS_TEMPLATE));
+            bar.set_message(name);
+            bar
+        });
+
+        return FileProgress {
+            state: self.state.clone(),
+            position: Arc::new(AtomicU64::new(0)),
+            bar: bar,
+        };
+    }
+
+    /// Counts a file that was already transferred in a previous run
+    pub fn skip_file(&self, len: u64) {
+        self.add_file(len);
+        self.state.skipped_files.fetch_add(1, Ordering::Relaxed);
+        self.state.complete_file();
+        self.state.add_bytes(len);
+    }
+
+    /// Removes the progress bars and prints a summary of the transfer
+    pub fn finish(&self) {
+        let state = &self.state;
+        if let Some(bars) = &state.bars {
+            bars.total.finish_and_clear();
+        }
+
+        let total_files = state.total_files.load(Ordering::Relaxed);
+        if total_files == 0 {
+            return;
+        }
+
+        let elapsed = state.started.elapsed();
+        let done_bytes = state.done_bytes.load(Ordering::Relaxed);
+        eprintln!(
+            "{} {} of {} files ({} skipped, {} failed), {} in {} ({}/s)",
+            state.action,
+            state.done_files.load(Ordering::Relaxed),
+            total_files,
+            state.skipped_files.load(Ordering::Relaxed),
+            state.failed_files.load(Ordering::Relaxed),
+            HumanBytes(done_bytes),
+            HumanDuration(elapsed),
+            HumanBytes(bytes_per_sec(done_bytes, elapsed)),
+        );
+    }
+
+    fn add_file(&self, len: u64) {
+        let state = &self.state;
+        let total_files = state.total_files.fetch_add(1, Ordering::Relaxed) + 1;
+        state.total_bytes.fetch_add(len, Ordering::Relaxed);
+
+        if let Some(bars) = &state.bars {
+            bars.total.inc_length(len);
+            bars.total.set_message(format!(
+                "{}/{}",
+                state.done_files.load(Ordering::Relaxed),
+                total_files
+            ));
+        }
+    }
+}
+
+impl FileProgress {
+    pub fn inc(&self, bytes: u64) {
+        self.position.fetch_add(bytes, Ordering::Relaxed);
+        self.state.add_bytes(bytes);
+        if let Some(bar) = &self.bar {
+            bar.inc(bytes);
+        }
+    }
+
+    /// Moves the progress to the given position, e.g. when a retried transfer starts over
+    pub fn set_position(&self, position: u64) {
+        let previous = self.position.swap(position, Ordering::Relaxed);
+        if position >= previous {
+            self.state.add_bytes(position - previous);
+        } else {
+            self.state.remove_bytes(previous - position);
+        }
+
+        if let Some(bar) = &self.bar {
+            bar.set_position(position);
+        }
+    }
+
+    pub fn finish(&self) {
+        self.state.complete_file();
+        if let Some(bar) = &self.bar {
+            bar.finish_and_clear();
+        }
+    }
+
+    /// Counts a file whose transfer or verification failed, it is not counted as done
+    pub fn fail(&self) {
+        self.state.failed_files.fetch_add(1, Ordering::Relaxed);
+        if let Some(bar) = &self.bar {
+            bar.finish_and_clear();
+        }
+    }
+}
+
+impl ProgressState {
+    fn add_bytes(&self, bytes: u64) {
+        self.done_bytes.fetch_add(bytes, Ordering::Relaxed);
+        if let Some(bars) = &self.bars {
+            bars.total.inc(bytes);
+        }
+    }
+
+    fn remove_bytes(&self, bytes: u64) {
+        let done_bytes = self.done_bytes.fetch_sub(bytes, Ordering::Relaxed) - bytes;
+        if let Some(bars) = &self.bars {
+            bars.total.set_position(done_bytes);
+        }
+    }
+
+    fn complete_file(&self) {
+        let done_files = self.done_files.fetch_add(1, Ordering::Relaxed) + 1;
+        if let Some(bars) = &self.bars {
+            bars.total.set_message(format!(
+                "{}/{}",
+                done_files,
+                self.total_files.load(Ordering::Relaxed)
+            ));
+        }
+    }
+}
+
+/// Logs the progress until the transfer is dropped
+async fn log_progress(state: Weak<ProgressState>) {
+    let mut interval = tokio::time::interval(PROGRESS_LOG_INTERVAL);
+    // the first tick completes immediately
+    interval.tick().await;
+
+    loop {
+        interval.tick().await;
+        let state = match state.upgrade() {
+            Some(state) => state,
+            None => return,
+        };
+
+        let total_bytes = state.total_bytes.load(Ordering::Relaxed);
+        let done_bytes = state.done_bytes.load(Ordering::Relaxed);
+        let rate = bytes_per_sec(done_bytes, state.started.elapsed());
+        let eta = match rate {
+            0 => "unknown".to_string(),
+            rate => HumanDuration(Duration::from_secs(
+                total_bytes.saturating_sub(done_bytes) / rate,
+            ))
+            .to_string(),
+        };
+
+        eprintln!(
+            "{} {}/{} files, {}/{} ({}/s), ETA {}",
+            state.action,
+            state.done_files.load(Ordering::Relaxed),
+            state.total_files.load(Ordering::Relaxed),
+            HumanBytes(done_bytes),
+            HumanBytes(total_bytes),
+            HumanBytes(rate),
+            eta,
+        );
+    }
+}
+
+fn bytes_per_sec(bytes: u64, elapsed: Duration) -> u64 {
+    match elapsed.as_secs_f64() {
+        secs if secs > 0.0 => (bytes as f64 / secs) as u64,
+        _ => 0,
+    }
+}
+
+fn progress_style(template: &str) -> ProgressStyle {
+    ProgressStyle::with_template(template)
+        .unwrap_or_else(|_| ProgressStyle::default_bar())
+        .progress_chars("=> ")
+}
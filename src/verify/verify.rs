@@ -0,0 +1,243 @@
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+};
+
+use async_channel::bounded;
+use futures::{future::try_join, StreamExt, TryStreamExt};
+use scienceobjectsdb_rust_api::sciobjectsdb::sciobjsdb::api::storage::models::v1::Object;
+
+use crate::{
+    client::client,
+    download::{
+        download_handler::{DownloadHandler, ObjectDownloadMessage},
+        download_path_handler::{DownloadPathHandler, DATASET_INDEX_DIR_NAME},
+        path_claims::PathClaims,
+    },
+    output::{
+        output::OutputWriter,
+        views::{VerifyFindingKind, VerifyFindingView},
+    },
+    util::{
+        checksum, cli,
+        error::{CliError, Result},
+    },
+};
+
+const VERIFY_OBJECT_QUEUE_SIZE: usize = 500;
+
+/// Compares a local directory tree with the objects of a remote resource
+pub struct Verify {
+    client: client::Client,
+    output: OutputWriter,
+}
+
+impl Verify {
+    pub fn new(client: client::Client, output: OutputWriter) -> Self {
+        return Verify {
+            client: client,
+            output: output,
+        };
+    }
+
+    pub async fn verify<T: DownloadPathHandler>(
+        &self,
+        request: cli::Verify,
+        path_handler: T,
+    ) -> Result<()> {
+        let basepath = PathBuf::from(request.path.as_str());
+        let path_claims = PathClaims::new(request.on_collision);
+        let (expected_files, skipped_object_groups) = self
+            .expected_files(&request, &path_handler, &path_claims, &basepath)
+            .await?;
+
+        let mut expected_paths: Vec<&PathBuf> = expected_files.keys().collect();
+        expected_paths.sort();
+        let mut findings: Vec<VerifyFindingView> = futures::stream::iter(expected_paths)
+            .map(|path| Verify::verify_file(path, &expected_files[path], request.size_only))
+            .buffered(request.workers())
+            .try_filter_map(|finding| async move { Ok(finding) })
+            .try_collect()
+            .await?;
+
+        // files below the directories of the resource that do not belong to any object, single
+        // objects can be loaded into any directory so other files are not reported
+        let object_group_paths: Vec<PathBuf> = expected_files
+            .keys()
+            .filter_map(|path| path.parent().map(|x| x.to_path_buf()))
+            .collect();
+        let root = match request.resource {
+            cli::Resource::Object => None,
+            _ => common_ancestor(&object_group_paths),
+        };
+        if let Some(root) = root {
+            let mut extra_files: Vec<PathBuf> = local_files(&root)
+                .await?
+                .into_iter()
+                .filter(|path| !expected_files.contains_key(path))
+                .collect();
+            extra_files.sort();
+
+            findings.extend(extra_files.into_iter().map(|path| VerifyFindingView {
+                path: path.display().to_string(),
+                kind: VerifyFindingKind::Extra,
+                expected: None,
+                actual: None,
+            }));
+        }
+
+        self.output.write_list(&findings)?;
+        path_claims.finish()?;
+
+        if !findings.is_empty() {
+            return Err(CliError::VerificationFailed(format!(
+                "{} of {} expected files differ from the remote resource",
+                findings.len(),
+                expected_files.len()
+            )));
+        }
+        if skipped_object_groups > 0 {
+            return Err(CliError::SkippedObjectGroups(skipped_object_groups));
+        }
+
+        Ok(())
+    }
+
+    /// Collects the objects of the resource by the path they are expected at, and the number
+    /// of object groups that were skipped
+    async fn expected_files<T: DownloadPathHandler>(
+        &self,
+        request: &cli::Verify,
+        path_handler: &T,
+        path_claims: &PathClaims,
+        basepath: &Path,
+    ) -> Result<(HashMap<PathBuf, Object>, u64)> {
+        let (sender, mut recv) = bounded::<ObjectDownloadMessage>(VERIFY_OBJECT_QUEUE_SIZE);
+
+        let resource_future = DownloadHandler::handle_resources(
+            request.resource.clone(),
+            cli::required_id(&request.id)?,
+            basepath,
+            &request.labels,
+            request.workers(),
+            self.client.clone(),
+            sender,
+        );
+        let collect_future = async move {
+            let mut object_msgs = Vec::new();
+            while let Some(object_msg) = recv.next().await {
+                object_msgs.push(object_msg);
+            }
+
+            Ok(object_msgs)
+        };
+
+        let (skipped_object_groups, object_msgs) =
+            try_join(resource_future, collect_future).await?;
+
+        // the paths are resolved like by load, which reserves them before the first claim
+        if path_claims.needs_reservations() {
+            for object_msg in object_msgs.iter() {
+                let file_path = object_msg.file_path(path_handler, basepath);
+                path_claims.reserve(file_path, &object_msg.object);
+            }
+        }
+        let mut expected_files = HashMap::new();
+        for object_msg in object_msgs {
+            let file_path = object_msg.file_path(path_handler, basepath);
+            if let Some(file_path) = path_claims.claim(file_path, &object_msg.object) {
+                expected_files.insert(file_path, object_msg.object);
+            }
+        }
+
+        return Ok((expected_files, skipped_object_groups));
+    }
+
+    async fn verify_file(
+        path: &Path,
+        object: &Object,
+        size_only: bool,
+    ) -> Result<Option<VerifyFindingView>> {
+        let metadata = match tokio::fs::metadata(path).await {
+            Ok(metadata) if metadata.is_file() => metadata,
+            _ => {
+                return Ok(Some(VerifyFindingView {
+                    path: path.display().to_string(),
+                    kind: VerifyFindingKind::Missing,
+                    expected: None,
+                    actual: None,
+                }))
+            }
+        };
+
+        if metadata.len() != object.content_len as u64 {
+            return Ok(Some(VerifyFindingView {
+                path: path.display().to_string(),
+                kind: VerifyFindingKind::SizeMismatch,
+                expected: Some(object.content_len.to_string()),
+                actual: Some(metadata.len().to_string()),
+            }));
+        }
+
+        if size_only {
+            return Ok(None);
+        }
+
+        if let Some(expected) = checksum::sha256_from_labels(&object.labels) {
+            let actual = checksum::sha256_file(path).await?;
+            if actual != expected {
+                return Ok(Some(VerifyFindingView {
+                    path: path.display().to_string(),
+                    kind: VerifyFindingKind::ChecksumMismatch,
+                    expected: Some(expected),
+                    actual: Some(actual),
+                }));
+            }
+        }
+
+        return Ok(None);
+    }
+}
+
+/// The deepest directory that contains all of the given directories
+fn common_ancestor(paths: &[PathBuf]) -> Option<PathBuf> {
+    let mut paths = paths.iter();
+    let mut ancestor = paths.next()?.clone();
+
+    for path in paths {
+        while !path.starts_with(&ancestor) {
+            if !ancestor.pop() {
+                return None;
+            }
+        }
+    }
+
+    return Some(ancestor);
+}
+
+/// All files below the directory, index directories are not part of the data and skipped
+async fn local_files(root: &Path) -> Result<HashSet<PathBuf>> {
+    let mut files = HashSet::new();
+    let mut dirs = vec![root.to_path_buf()];
+
+    while let Some(dir) = dirs.pop() {
+        let mut entries = match tokio::fs::read_dir(&dir).await {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(err) => return Err(err.into()),
+        };
+
+        while let Some(entry) = entries.next_entry().await? {
+            let file_type = entry.file_type().await?;
+            if file_type.is_dir() {
+                if entry.file_name() != DATASET_INDEX_DIR_NAME {
+                    dirs.push(entry.path());
+                }
+            } else {
+                files.insert(entry.path());
+            }
+        }
+    }
+
+    return Ok(files);
+}